@@ -4,14 +4,22 @@ struct StatusBarMaterial {
     foreground_color: vec4<f32>,
     background_color: vec4<f32>,
     percent: f32,
+    segments: f32,
 };
 
+// Width of the gaps between segments, as a fraction of a segment.
+const SEGMENT_GAP: f32 = 0.08;
+
 
 @group(2) @binding(0) var<uniform> material: StatusBarMaterial;
 
 @fragment
 fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
 
+    let segment = fract(mesh.uv.x * material.segments);
+    if (material.segments > 1.0 && segment > 1.0 - SEGMENT_GAP) {
+        return material.background_color;
+    }
     if (mesh.uv.x <= material.percent) {
         return material.foreground_color;
     } else {
@@ -4,10 +4,122 @@ use avian2d::prelude::*;
 use bevy::{dev_tools::states::log_transitions, prelude::*};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use crate::screen::Screen;
+use crate::{
+    game::{
+        block::BlockEvent,
+        combat_log::CombatLog,
+        damage_zone::{DamageZoneEntered, DamageZoneExited},
+        dash::PerfectDodgeEvent,
+        death::DeathEvent,
+        health::{DamageDealtEvent, HealedEvent},
+    },
+    screen::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Print state transitions in dev builds
     app.add_systems(Update, log_transitions::<Screen>);
+    // Print the damage and healing that actually landed, who died to it,
+    // and who walks in and out of damage zones
+    app.add_systems(
+        Update,
+        (
+            log_damage_dealt,
+            log_healing,
+            log_deaths,
+            log_damage_zone_overlaps,
+            log_blocks,
+            log_perfect_dodges,
+        ),
+    );
+    // Dump the combat log of each run to a file
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(OnExit(Screen::Playing), dump_combat_log);
     app.add_plugins((WorldInspectorPlugin::new(), PhysicsDebugPlugin::default()));
 }
+
+fn log_damage_dealt(mut events: EventReader<DamageDealtEvent>) {
+    for event in events.read() {
+        debug!(
+            "{:?} dealt {:.1} {:?} damage to {:?} ({:.1} absorbed){}",
+            event.source,
+            event.amount,
+            event.kind,
+            event.target,
+            event.absorbed,
+            if event.critical { ", critical" } else { "" }
+        );
+    }
+}
+
+fn log_healing(mut events: EventReader<HealedEvent>) {
+    for event in events.read() {
+        debug!(
+            "{:?} healed {:?} for {:.1} ({:.1} overheal)",
+            event.source, event.target, event.amount, event.overheal
+        );
+    }
+}
+
+fn log_deaths(mut events: EventReader<DeathEvent>) {
+    for event in events.read() {
+        debug!("{:?} was killed by {:?}", event.entity, event.killer);
+    }
+}
+
+/// Write the combat log of the run as CSV, one hit per line.
+#[cfg(not(target_family = "wasm"))]
+fn dump_combat_log(combat_log: Res<CombatLog>) {
+    use std::io::Write;
+
+    const PATH: &str = "combat_log.csv";
+    let result = std::fs::File::create(PATH).and_then(|file| {
+        let mut writer = std::io::BufWriter::new(file);
+        writeln!(writer, "time,source,attack,target,amount,kind,killing_blow")?;
+        for entry in combat_log.entries() {
+            writeln!(
+                writer,
+                "{:.3},{},{},{},{:.2},{:?},{}",
+                entry.time.as_secs_f32(),
+                entry.source_name,
+                entry.attack_name,
+                entry.target_name,
+                entry.amount,
+                entry.kind,
+                entry.killing_blow
+            )?;
+        }
+        writer.flush()
+    });
+    match result {
+        Ok(()) => info!("Wrote {} hits to {PATH}", combat_log.entries().len()),
+        Err(error) => warn!("Could not write {PATH}: {error}"),
+    }
+}
+
+fn log_damage_zone_overlaps(
+    mut entered_events: EventReader<DamageZoneEntered>,
+    mut exited_events: EventReader<DamageZoneExited>,
+) {
+    for event in entered_events.read() {
+        debug!("{:?} entered damage zone {:?}", event.entity, event.zone);
+    }
+    for event in exited_events.read() {
+        debug!("{:?} left damage zone {:?}", event.entity, event.zone);
+    }
+}
+
+fn log_blocks(mut events: EventReader<BlockEvent>) {
+    for event in events.read() {
+        debug!(
+            "{:?} {:?} a hit from {:?}",
+            event.target, event.guard, event.source
+        );
+    }
+}
+
+fn log_perfect_dodges(mut events: EventReader<PerfectDodgeEvent>) {
+    for event in events.read() {
+        debug!("{:?} dodged a hit from {:?}", event.entity, event.source);
+    }
+}
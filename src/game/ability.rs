@@ -0,0 +1,592 @@
+//! Abilities cast with qi, on top of the equipped weapon's attacks.
+//!
+//! Each ability is plain data: a cost, a cast time, a cooldown and a list of [`AbilityEffect`]s.
+//! Adding an ability means adding a constructor here, no new systems.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use super::{
+    attack::AttackController,
+    damage_zone::DamageZoneBundle,
+    dash::Dash,
+    death::Dying,
+    health::{DamageDealtEvent, DamageKind},
+    hitbox::{Hitbox, HitboxShape},
+    input::PlayerAction,
+    knockback::{Hitstun, Knockback},
+    projectile::{ProjectileBundle, ProjectileDefinition},
+    status_effect::{InflictsStatusEffect, Stacking, StatusEffect, StatusEffectKind},
+    ui::{
+        percentage::{AsPercentage, Percentage},
+        status_bar::definition::{Size, StatusBarDefinition},
+    },
+    GameLayer,
+};
+use crate::{game::spawn::player::Player, AppSet};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<AbilityController>();
+    app.add_systems(
+        Update,
+        record_ability_controller.in_set(AppSet::RecordInput),
+    );
+    app.register_type::<Abilities>();
+    app.register_type::<Casting>();
+    app.observe(refund_interrupted_cast);
+    app.register_type::<Qi>();
+    app.register_type::<Buffs>();
+    app.add_systems(
+        Update,
+        (
+            (tick_abilities, regenerate_qi, tick_buffs).in_set(AppSet::TickTimers),
+            (apply_abilities, cancel_casting_on_hit).in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// Which of an entity's two abilities is used.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbilitySlot {
+    Primary,
+    Secondary,
+}
+
+/// A temporary boost to the caster.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct BuffDefinition {
+    pub duration: Duration,
+    /// Added to the damage of every hit dealt, as a fraction.
+    pub damage_percent: f32,
+    pub move_speed_multiplier: f32,
+}
+
+/// Something that happens when a cast finishes.
+#[derive(Reflect, Clone, Debug)]
+pub enum AbilityEffect {
+    /// A hitbox around the caster, facing the look direction.
+    DamageZone {
+        damage: f32,
+        kind: DamageKind,
+        knockback: Option<Knockback>,
+        status_effect: Option<StatusEffect>,
+        hitbox: Hitbox,
+        lifetime: Duration,
+        color: Color,
+    },
+    /// A projectile fired in the look direction.
+    Projectile {
+        damage: f32,
+        kind: DamageKind,
+        knockback: Option<Knockback>,
+        status_effect: Option<StatusEffect>,
+        projectile: ProjectileDefinition,
+        color: Color,
+    },
+    Buff(BuffDefinition),
+    /// A dash in the movement direction. It doesn't use a dash charge.
+    Dash {
+        speed: f32,
+        duration: Duration,
+    },
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct AbilityDefinition {
+    pub name: String,
+    /// Qi spent when the cast starts. An interrupted cast gives it back.
+    pub cost: f32,
+    /// Time between the press and the effects happening.
+    pub cast_time: Duration,
+    /// Time after the cast before the ability can be used again.
+    pub cooldown: Duration,
+    pub effects: Vec<AbilityEffect>,
+}
+
+impl AbilityDefinition {
+    /// Dash forward and move and hit harder for a while.
+    pub fn wind_step() -> Self {
+        AbilityDefinition {
+            name: "WindStep".to_string(),
+            cost: 15.0,
+            cast_time: Duration::ZERO,
+            cooldown: Duration::from_secs(3),
+            effects: vec![
+                AbilityEffect::Dash {
+                    speed: 900.0,
+                    duration: Duration::from_millis(150),
+                },
+                AbilityEffect::Buff(BuffDefinition {
+                    duration: Duration::from_secs(3),
+                    damage_percent: 0.25,
+                    move_speed_multiplier: 1.3,
+                }),
+            ],
+        }
+    }
+
+    /// Burn everything around the caster and throw a fire talisman forward.
+    pub fn fire_lotus() -> Self {
+        let burn = StatusEffect {
+            kind: StatusEffectKind::Burn,
+            duration: Duration::from_secs(3),
+            tick_interval: Duration::from_millis(500),
+            magnitude: 2.0,
+            stacking: Stacking::Refresh,
+        };
+        AbilityDefinition {
+            name: "FireLotus".to_string(),
+            cost: 35.0,
+            cast_time: Duration::from_millis(400),
+            cooldown: Duration::from_secs(5),
+            effects: vec![
+                AbilityEffect::DamageZone {
+                    damage: 15.0,
+                    kind: DamageKind::Fire,
+                    knockback: Some(Knockback {
+                        strength: 350.0,
+                        hitstun: Duration::from_millis(250),
+                    }),
+                    status_effect: Some(burn),
+                    hitbox: Hitbox::new(
+                        HitboxShape::Ring {
+                            inner_radius: 20.0,
+                            outer_radius: 90.0,
+                        },
+                        Vec2::ZERO,
+                    ),
+                    lifetime: Duration::from_millis(200),
+                    color: Color::srgba(1.0, 0.45, 0.1, 0.5),
+                },
+                AbilityEffect::Projectile {
+                    damage: 10.0,
+                    kind: DamageKind::Fire,
+                    knockback: None,
+                    status_effect: Some(burn),
+                    projectile: ProjectileDefinition {
+                        speed: 300.0,
+                        radius: 10.0,
+                        lifetime: Duration::from_secs(2),
+                        pierce: 2,
+                        bounces: 0,
+                        homing: 2.0,
+                        on_hit: None,
+                        on_expire: None,
+                    },
+                    color: Color::srgb(1.0, 0.6, 0.2),
+                },
+            ],
+        }
+    }
+}
+
+/// An ability and its cooldown.
+#[derive(Reflect)]
+pub struct Ability {
+    pub definition: AbilityDefinition,
+    pub cooldown: Duration,
+}
+
+impl Ability {
+    pub fn new(definition: AbilityDefinition) -> Self {
+        Ability {
+            definition,
+            cooldown: Duration::ZERO,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown.is_zero()
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Abilities {
+    pub primary: Option<Ability>,
+    pub secondary: Option<Ability>,
+}
+
+impl Abilities {
+    pub fn new(primary: AbilityDefinition, secondary: AbilityDefinition) -> Self {
+        Abilities {
+            primary: Some(Ability::new(primary)),
+            secondary: Some(Ability::new(secondary)),
+        }
+    }
+
+    pub fn get(&self, slot: AbilitySlot) -> Option<&Ability> {
+        match slot {
+            AbilitySlot::Primary => self.primary.as_ref(),
+            AbilitySlot::Secondary => self.secondary.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, slot: AbilitySlot) -> Option<&mut Ability> {
+        match slot {
+            AbilitySlot::Primary => self.primary.as_mut(),
+            AbilitySlot::Secondary => self.secondary.as_mut(),
+        }
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct AbilityController {
+    /// The slot whose button was pressed this frame.
+    pub intent: Option<AbilitySlot>,
+}
+
+fn record_ability_controller(
+    action_state: Res<ActionState<PlayerAction>>,
+    mut query: Query<&mut AbilityController, With<Player>>,
+) {
+    for mut ability_controller in &mut query {
+        ability_controller.intent = if action_state.just_pressed(&PlayerAction::AbilityPrimary) {
+            Some(AbilitySlot::Primary)
+        } else if action_state.just_pressed(&PlayerAction::AbilitySeconary) {
+            Some(AbilitySlot::Secondary)
+        } else {
+            None
+        };
+    }
+}
+
+/// The resource spent by abilities. Regenerates over time.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Qi {
+    pub points: f32,
+    pub max: f32,
+    /// Points per second.
+    pub regeneration: f32,
+}
+
+impl AsPercentage for Qi {
+    fn percentage(&self) -> Percentage {
+        Percentage::new(self.points / self.max)
+    }
+}
+
+impl Qi {
+    pub fn new(max: f32, regeneration: f32) -> Self {
+        Qi {
+            points: max,
+            max,
+            regeneration,
+        }
+    }
+
+    pub fn can_afford(&self, cost: f32) -> bool {
+        self.points >= cost
+    }
+
+    /// Spend `cost` if there is enough qi. Returns whether it was spent.
+    fn spend(&mut self, cost: f32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        self.points -= cost;
+        true
+    }
+
+    fn refund(&mut self, cost: f32) {
+        self.points = f32::min(self.points + cost, self.max);
+    }
+}
+
+/// An entity casting an ability.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Casting {
+    pub slot: AbilitySlot,
+    /// Qi already spent on the cast.
+    pub cost: f32,
+    pub cast_time: Duration,
+    pub elapsed: Duration,
+}
+
+impl AsPercentage for Casting {
+    fn percentage(&self) -> Percentage {
+        if self.cast_time.is_zero() {
+            return Percentage::new(1.0);
+        }
+        Percentage::new(self.elapsed.as_secs_f32() / self.cast_time.as_secs_f32())
+    }
+}
+
+/// The cast and its bar, inserted and removed together.
+#[derive(Bundle)]
+pub struct CastingBundle {
+    pub casting: Casting,
+    pub cast_bar: StatusBarDefinition<Casting>,
+}
+
+impl CastingBundle {
+    pub fn new(slot: AbilitySlot, cost: f32, cast_time: Duration) -> Self {
+        CastingBundle {
+            casting: Casting {
+                slot,
+                cost,
+                cast_time,
+                elapsed: Duration::ZERO,
+            },
+            cast_bar: StatusBarDefinition {
+                size: Size::new(40.0, 3.0),
+                offset: Vec3::new(0.0, 38.0, 10.0),
+                foreground_color: Color::srgb(0.6, 0.3, 1.0),
+                ..default()
+            },
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+struct ActiveBuff {
+    buff: BuffDefinition,
+    remaining: Duration,
+}
+
+/// The buffs running on an entity.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Buffs(Vec<ActiveBuff>);
+
+impl Buffs {
+    pub fn apply(&mut self, buff: BuffDefinition) {
+        self.0.push(ActiveBuff {
+            buff,
+            remaining: buff.duration,
+        });
+    }
+
+    pub fn damage_percent(&self) -> f32 {
+        self.0.iter().map(|active| active.buff.damage_percent).sum()
+    }
+
+    pub fn move_speed_multiplier(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|active| active.buff.move_speed_multiplier)
+            .product()
+    }
+}
+
+fn tick_abilities(time: Res<Time>, mut query: Query<(&mut Abilities, Option<&mut Casting>)>) {
+    for (mut abilities, casting) in &mut query {
+        let abilities = &mut *abilities;
+        for ability in [&mut abilities.primary, &mut abilities.secondary]
+            .into_iter()
+            .flatten()
+        {
+            ability.cooldown = ability.cooldown.saturating_sub(time.delta());
+        }
+        if let Some(mut casting) = casting {
+            casting.elapsed += time.delta();
+        }
+    }
+}
+
+fn regenerate_qi(time: Res<Time>, mut query: Query<&mut Qi, Without<Dying>>) {
+    for mut qi in &mut query {
+        qi.points = f32::min(qi.points + qi.regeneration * time.delta_seconds(), qi.max);
+    }
+}
+
+fn tick_buffs(time: Res<Time>, mut query: Query<&mut Buffs>) {
+    for mut buffs in &mut query {
+        for active in &mut buffs.0 {
+            active.remaining = active.remaining.saturating_sub(time.delta());
+        }
+        buffs.0.retain(|active| !active.remaining.is_zero());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_abilities(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &AbilityController,
+            &mut Abilities,
+            &mut Qi,
+            Option<&Casting>,
+            Option<&AttackController>,
+            Option<&mut Dash>,
+            Option<&mut Buffs>,
+        ),
+        (Without<Dying>, Without<Hitstun>),
+    >,
+) {
+    for (
+        entity,
+        transform,
+        controller,
+        mut abilities,
+        mut qi,
+        casting,
+        attack_controller,
+        mut dash,
+        mut buffs,
+    ) in &mut query
+    {
+        let slot = match casting {
+            Some(casting) if casting.elapsed >= casting.cast_time => {
+                commands.entity(entity).remove::<CastingBundle>();
+                casting.slot
+            }
+            Some(_) => continue,
+            None => {
+                let Some(slot) = controller.intent else {
+                    continue;
+                };
+                let Some(ability) = abilities.get(slot) else {
+                    continue;
+                };
+                if !ability.is_ready() || !qi.spend(ability.definition.cost) {
+                    continue;
+                }
+                if !ability.definition.cast_time.is_zero() {
+                    commands.entity(entity).insert(CastingBundle::new(
+                        slot,
+                        ability.definition.cost,
+                        ability.definition.cast_time,
+                    ));
+                    continue;
+                }
+                slot
+            }
+        };
+
+        let Some(ability) = abilities.get_mut(slot) else {
+            continue;
+        };
+        ability.cooldown = ability.definition.cooldown;
+
+        let look_direction = attack_controller.map_or(Vec2::X, |c| c.look_direction);
+        let transform = Transform {
+            translation: transform.translation,
+            rotation: Quat::from_rotation_z(Vec2::X.angle_between(look_direction)),
+            ..default()
+        };
+        for effect in &ability.definition.effects {
+            match effect {
+                AbilityEffect::DamageZone {
+                    damage,
+                    kind,
+                    knockback,
+                    status_effect,
+                    hitbox,
+                    lifetime,
+                    color,
+                } => {
+                    let mut damage_zone = DamageZoneBundle::new(
+                        entity,
+                        *damage,
+                        *kind,
+                        *lifetime,
+                        hitbox.clone(),
+                        GameLayer::PlayerHitbox,
+                        GameLayer::Enemies,
+                    )
+                    .with_melee();
+                    if let Some(knockback) = knockback {
+                        damage_zone = damage_zone.with_knockback(*knockback);
+                    }
+                    let mut zone = commands.spawn((
+                        Name::new(ability.definition.name.clone()),
+                        damage_zone,
+                        effect_sprite(*color, hitbox.radius(), transform),
+                    ));
+                    if let Some(effect) = status_effect {
+                        zone.insert(InflictsStatusEffect(*effect));
+                    }
+                }
+                AbilityEffect::Projectile {
+                    damage,
+                    kind,
+                    knockback,
+                    status_effect,
+                    projectile,
+                    color,
+                } => {
+                    let mut bundle = ProjectileBundle::new(
+                        entity,
+                        *damage,
+                        *kind,
+                        *projectile,
+                        look_direction,
+                        GameLayer::PlayerHitbox,
+                        GameLayer::Enemies,
+                    );
+                    if let Some(knockback) = knockback {
+                        bundle = bundle.with_knockback(*knockback);
+                    }
+                    let mut projectile_entity = commands.spawn((
+                        Name::new(ability.definition.name.clone()),
+                        bundle,
+                        effect_sprite(*color, projectile.radius, transform),
+                    ));
+                    if let Some(effect) = status_effect {
+                        projectile_entity.insert(InflictsStatusEffect(*effect));
+                    }
+                }
+                AbilityEffect::Buff(buff) => match &mut buffs {
+                    Some(buffs) => buffs.apply(*buff),
+                    None => {
+                        let mut new_buffs = Buffs::default();
+                        new_buffs.apply(*buff);
+                        commands.entity(entity).insert(new_buffs);
+                    }
+                },
+                AbilityEffect::Dash { speed, duration } => {
+                    if let Some(dash) = &mut dash {
+                        dash.start(*speed, *duration);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn effect_sprite(color: Color, radius: f32, transform: Transform) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(radius * 2.0)),
+            ..default()
+        },
+        transform,
+        ..default()
+    }
+}
+
+/// Casts removed before they finish, by a hit or a dash, give their qi back.
+fn refund_interrupted_cast(
+    trigger: Trigger<OnRemove, Casting>,
+    mut query: Query<(&Casting, &mut Qi)>,
+) {
+    let Ok((casting, mut qi)) = query.get_mut(trigger.entity()) else {
+        return;
+    };
+    if casting.elapsed < casting.cast_time {
+        qi.refund(casting.cost);
+    }
+}
+
+/// Getting hit interrupts the cast. Damage over time doesn't count as a hit.
+fn cancel_casting_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<DamageDealtEvent>,
+    query: Query<(), With<Casting>>,
+) {
+    for event in events.read() {
+        if !event.damage_over_time && query.contains(event.target) {
+            commands.entity(event.target).remove::<CastingBundle>();
+        }
+    }
+}
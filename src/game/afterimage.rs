@@ -0,0 +1,117 @@
+//! Fading copies of a sprite left behind while dashing, to make dashes easier to read.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::dash::Dash;
+use crate::{screen::Screen, AppSet};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<AfterimageTrail>();
+    app.register_type::<Afterimage>();
+    app.add_systems(
+        Update,
+        (spawn_afterimages, fade_afterimages).in_set(AppSet::Update),
+    );
+}
+
+/// Leaves afterimages of the entity's sprite behind while it dashes.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct AfterimageTrail {
+    /// Time between two afterimages.
+    pub interval: Duration,
+    /// Tint of the afterimages when they spawn. Its alpha fades to zero.
+    pub color: Color,
+    /// How long an afterimage takes to fade out.
+    pub fade_time: Duration,
+    /// Time left before the next afterimage.
+    next: Duration,
+}
+
+impl AfterimageTrail {
+    pub fn new(interval: Duration, color: Color, fade_time: Duration) -> Self {
+        AfterimageTrail {
+            interval,
+            color,
+            fade_time,
+            next: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Afterimage {
+    color: Color,
+    fade_time: Duration,
+    remaining: Duration,
+}
+
+fn spawn_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        &mut AfterimageTrail,
+        &Dash,
+        &Sprite,
+        &Handle<Image>,
+        Option<&TextureAtlas>,
+        &GlobalTransform,
+    )>,
+) {
+    for (mut trail, dash, sprite, texture, atlas, global_transform) in &mut query {
+        if !dash.is_dashing() {
+            // The next dash leaves an afterimage right away.
+            trail.next = Duration::ZERO;
+            continue;
+        }
+        trail.next = trail.next.saturating_sub(time.delta());
+        if !trail.next.is_zero() {
+            continue;
+        }
+        trail.next = trail.interval;
+
+        let mut transform = global_transform.compute_transform();
+        // Behind the entity that left it.
+        transform.translation.z -= 0.01;
+        let mut afterimage = commands.spawn((
+            Name::new("Afterimage"),
+            Afterimage {
+                color: trail.color,
+                fade_time: trail.fade_time,
+                remaining: trail.fade_time,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: trail.color,
+                    ..sprite.clone()
+                },
+                texture: texture.clone(),
+                transform,
+                ..default()
+            },
+            StateScoped(Screen::Playing),
+        ));
+        if let Some(atlas) = atlas {
+            afterimage.insert(atlas.clone());
+        }
+    }
+}
+
+fn fade_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Afterimage, &mut Sprite)>,
+) {
+    for (entity, mut afterimage, mut sprite) in &mut query {
+        afterimage.remaining = afterimage.remaining.saturating_sub(time.delta());
+        if afterimage.remaining.is_zero() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let fade = afterimage.remaining.as_secs_f32() / afterimage.fade_time.as_secs_f32();
+        sprite.color = afterimage.color.with_alpha(afterimage.color.alpha() * fade);
+    }
+}
@@ -192,32 +192,26 @@ fn update_attack_animation_atlas(mut query: Query<(&AttackAnimation, &mut Textur
 pub struct AttackAnimation {
     timer: Timer,
     frame: usize,
+    frames: usize,
 }
 
 impl AttackAnimation {
-    /// The number of frames.
-    const FRAMES: usize = 6;
-    /// The duration of each frame.
-    const INTERVAL: Duration = Duration::from_millis(60);
-
-    fn animating() -> Self {
+    /// An animation of `frames` frames, each shown for `interval`.
+    pub fn new(frames: usize, interval: Duration) -> Self {
         Self {
-            timer: Timer::new(Self::INTERVAL, TimerMode::Once),
+            timer: Timer::new(interval, TimerMode::Once),
             frame: 0,
+            frames,
         }
     }
 
-    pub fn new() -> Self {
-        Self::animating()
-    }
-
     /// Update animation timers.
     pub fn update_timer(&mut self, delta: Duration) {
         self.timer.tick(delta);
         if !self.timer.finished() {
             return;
         }
-        self.frame = (self.frame + 1) % Self::FRAMES;
+        self.frame = (self.frame + 1) % self.frames;
     }
 
     /// Whether animation changed this tick.
@@ -1,10 +1,18 @@
 use std::time::Duration;
 
 use super::{
+    ability::{Casting, CastingBundle},
     animation::AttackAnimation,
     assets::{HandleMap, ImageKey},
+    cancel::{BusyState, CancelAction, CancelRules},
+    charge::{Charging, ChargingBundle},
+    combo::Combo,
     damage_zone::DamageZoneBundle,
+    dash::{self, Dash},
+    death::Dying,
     input::PlayerAction,
+    projectile::ProjectileBundle,
+    weapon::{AttackDefinition, AttackSlot, WeaponInventory},
     GameLayer,
 };
 use bevy::prelude::*;
@@ -13,18 +21,50 @@ use leafwing_input_manager::prelude::*;
 use super::spawn::player::Player;
 use crate::AppSet;
 
+/// How long a press made while attacking is kept, to fire once the attacker is ready.
+const INPUT_BUFFER: Duration = Duration::from_millis(200);
+
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<AttackController>();
     app.add_systems(Update, record_attack_controller.in_set(AppSet::RecordInput));
     app.register_type::<Attack>();
-    app.add_systems(Update, apply_attack.in_set(AppSet::Update));
+    app.add_systems(
+        Update,
+        apply_attack.in_set(AppSet::Update).after(dash::apply_dash),
+    );
 }
 
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct AttackController {
     pub look_direction: Vec2,
-    pub intent: bool,
+    /// The slot whose button was pressed this frame.
+    pub intent: Option<AttackSlot>,
+    /// Whether the primary attack button is held down.
+    pub held_primary: bool,
+    /// Whether the secondary attack button is held down.
+    pub held_secondary: bool,
+    pub switch_weapon: bool,
+    /// Whether attacking is suppressed this frame, e.g. by a stun. Cancels a charging attack.
+    pub suppressed: bool,
+}
+
+impl AttackController {
+    pub fn is_held(&self, slot: AttackSlot) -> bool {
+        match slot {
+            AttackSlot::Primary => self.held_primary,
+            AttackSlot::Secondary => self.held_secondary,
+        }
+    }
+
+    /// Drop this frame's attack presses and held buttons, and cancel a charging attack
+    /// instead of releasing it.
+    pub fn clear_intent(&mut self) {
+        self.intent = None;
+        self.held_primary = false;
+        self.held_secondary = false;
+        self.suppressed = true;
+    }
 }
 
 fn record_attack_controller(
@@ -40,61 +80,489 @@ fn record_attack_controller(
             attack_controller.look_direction = look_intent.normalize_or_zero();
         }
 
-        attack_controller.intent = action_state.just_pressed(&PlayerAction::AttackPrimary)
-            || action_state.just_pressed(&PlayerAction::AttackSecondary);
+        attack_controller.intent = if action_state.just_pressed(&PlayerAction::AttackPrimary) {
+            Some(AttackSlot::Primary)
+        } else if action_state.just_pressed(&PlayerAction::AttackSecondary) {
+            Some(AttackSlot::Secondary)
+        } else {
+            None
+        };
+        attack_controller.held_primary = action_state.pressed(&PlayerAction::AttackPrimary);
+        attack_controller.held_secondary = action_state.pressed(&PlayerAction::AttackSecondary);
+        attack_controller.switch_weapon = action_state.just_pressed(&PlayerAction::SwitchWeapon);
+        attack_controller.suppressed = false;
     }
 }
 
-#[derive(Component, Reflect, Default)]
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub enum AttackState {
+    #[default]
+    Ready,
+    Windup(Duration),
+    Active(Duration),
+    Recovery(Duration),
+}
+
+#[derive(Component, Reflect)]
 #[reflect(Component)]
-pub struct Attack;
+pub struct Attack {
+    pub state: AttackState,
+    /// Divides the windup, active, recovery and slot cooldown times. `2.0` attacks twice as fast.
+    pub speed: f32,
+    /// The attack being performed, with its timings already scaled by `speed`.
+    current: Option<AttackDefinition>,
+    /// A press made while busy, and how much longer it is kept.
+    buffered: Option<(AttackSlot, Duration)>,
+}
+
+impl Attack {
+    pub fn new(speed: f32) -> Attack {
+        Attack {
+            state: AttackState::Ready,
+            speed,
+            current: None,
+            buffered: None,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == AttackState::Ready
+    }
+
+    /// Start the windup of an attack.
+    /// Returns whether the hitbox has to be spawned right away.
+    fn start(&mut self, attack: &AttackDefinition) -> bool {
+        let speed = self.speed.max(f32::EPSILON);
+        let mut attack = attack.clone();
+        for duration in [
+            &mut attack.windup,
+            &mut attack.lifetime,
+            &mut attack.recovery,
+            &mut attack.frame_interval,
+        ] {
+            *duration = duration.div_f32(speed);
+        }
+        self.state = AttackState::Windup(attack.windup);
+        self.current = Some(attack);
+        self.settle()
+    }
+
+    /// Advance the state. Returns whether the windup just ended.
+    fn apply_delta_time(&mut self, delta_time: Duration) -> bool {
+        if let Some((_, remaining)) = &mut self.buffered {
+            *remaining = remaining.saturating_sub(delta_time);
+            if remaining.is_zero() {
+                self.buffered = None;
+            }
+        }
+        match &mut self.state {
+            AttackState::Windup(duration)
+            | AttackState::Active(duration)
+            | AttackState::Recovery(duration) => {
+                *duration = duration.saturating_sub(delta_time);
+            }
+            AttackState::Ready => (),
+        }
+        self.settle()
+    }
+
+    /// Move past states that have no time left. Returns whether the windup ended.
+    fn settle(&mut self) -> bool {
+        let mut windup_ended = false;
+        loop {
+            let Some(attack) = &self.current else {
+                self.state = AttackState::Ready;
+                return windup_ended;
+            };
+            self.state = match self.state {
+                AttackState::Windup(duration) if duration.is_zero() => {
+                    windup_ended = true;
+                    AttackState::Active(attack.lifetime)
+                }
+                AttackState::Active(duration) if duration.is_zero() => {
+                    AttackState::Recovery(attack.recovery)
+                }
+                AttackState::Recovery(duration) if duration.is_zero() => {
+                    self.current = None;
+                    AttackState::Ready
+                }
+                _ => return windup_ended,
+            };
+        }
+    }
+
+    pub fn busy_state(&self) -> Option<BusyState> {
+        match self.state {
+            AttackState::Windup(_) => Some(BusyState::AttackWindup),
+            AttackState::Active(_) => Some(BusyState::AttackActive),
+            AttackState::Recovery(_) => Some(BusyState::AttackRecovery),
+            AttackState::Ready => None,
+        }
+    }
+
+    /// Interrupt the attack. Its slot keeps cooling down.
+    /// An attack cancelled during its windup never hits.
+    pub fn cancel(&mut self) {
+        self.current = None;
+        self.settle();
+    }
+
+    /// Keep a press made while busy, to use it once ready.
+    fn buffer(&mut self, slot: AttackSlot) {
+        self.buffered = Some((slot, INPUT_BUFFER));
+    }
 
+    fn take_buffered(&mut self) -> Option<AttackSlot> {
+        self.buffered.take().map(|(slot, _)| slot)
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn apply_attack(
     mut commands: Commands,
+    time: Res<Time>,
     image_handles: Res<HandleMap<ImageKey>>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut attack_query: Query<(Entity, &Transform, &AttackController, &Attack)>,
+    mut attack_query: Query<
+        (
+            Entity,
+            &Transform,
+            &AttackController,
+            &mut WeaponInventory,
+            &mut Attack,
+            &mut Combo,
+            Option<&Charging>,
+            Option<&mut Dash>,
+            Option<&Casting>,
+            Option<&CancelRules>,
+        ),
+        Without<Dying>,
+    >,
 ) {
-    for (entity, transform, controller, _) in &mut attack_query {
-        let attack_transform = Transform {
-            translation: transform.translation,
-            rotation: Quat::from_rotation_z(Vec2::X.angle_between(controller.look_direction)),
-            scale: Vec2::splat(2.0).extend(1.0),
-        };
+    for (
+        entity,
+        transform,
+        controller,
+        mut inventory,
+        mut attack,
+        mut combo,
+        charging,
+        mut dash,
+        casting,
+        cancel_rules,
+    ) in &mut attack_query
+    {
+        let mut spawn = attack.apply_delta_time(time.delta());
+        // Dashes and casts can only be attacked out of when the rules allow it.
+        let dash_state = dash.as_ref().and_then(|dash| dash.busy_state());
+        let casting_state = casting.map(|_| BusyState::Casting);
+        let busy_blocks_attack = [dash_state, casting_state]
+            .into_iter()
+            .flatten()
+            .any(|state| !cancel_rules.is_some_and(|r| r.allows(state, CancelAction::Attack)));
 
-        let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 1, Some(UVec2::ZERO), None);
-        let texture_atlas_layout = texture_atlas_layouts.add(layout);
-        let attack_animation = AttackAnimation::new();
-
-        if controller.intent {
-            commands
-                .spawn((
-                    Name::new("BaseAttack"),
-                    DamageZoneBundle::new(
-                        entity,
-                        10.0,
-                        Duration::from_millis(180),
-                        32.0,
-                        GameLayer::PlayerHitbox,
-                        GameLayer::Enemies,
-                    ),
-                    TransformBundle::from_transform(attack_transform),
-                    InheritedVisibility::VISIBLE,
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        SpriteBundle {
-                            transform: Transform::from_translation((Vec2::X * 50.0).extend(10.0)),
-                            texture: image_handles[&ImageKey::BaseAttack].clone_weak(),
-                            ..default()
-                        },
-                        TextureAtlas {
-                            layout: texture_atlas_layout.clone(),
-                            index: attack_animation.get_atlas_index(),
-                        },
-                        attack_animation,
-                    ));
-                });
+        if let Some(weapon) = inventory.equipped_mut() {
+            let speed = attack.speed;
+            if controller.suppressed {
+                // Being stunned or raising the guard cancels the charge, it doesn't fire.
+                if charging.is_some() {
+                    commands.entity(entity).remove::<ChargingBundle>();
+                }
+            } else if let Some(charging) = charging {
+                // While charging, the only thing to do is releasing the charged attack.
+                if !controller.is_held(charging.slot) {
+                    commands.entity(entity).remove::<ChargingBundle>();
+                    if let Some(definition) = weapon.try_attack(charging.slot, charging.step, speed)
+                    {
+                        let definition = charging.charged(definition);
+                        combo.start(charging.slot, charging.step, definition.chain_window);
+                        spawn |= attack.start(&definition);
+                    }
+                }
+            } else if !attack.is_ready() || busy_blocks_attack {
+                if let Some(slot) = controller.intent {
+                    attack.buffer(slot);
+                }
+            } else if let Some(slot) = controller.intent.or_else(|| attack.take_buffered()) {
+                if casting.is_some() {
+                    commands.entity(entity).remove::<CastingBundle>();
+                }
+                // Only a press made during the window counts, not one buffered before the dash.
+                let dash_attack = (controller.intent.is_some()
+                    && dash.as_ref().is_some_and(|d| d.in_dash_attack_window()))
+                .then(|| weapon.try_dash_attack(speed))
+                .flatten();
+                if let Some(definition) = dash_attack {
+                    if let Some(dash) = &mut dash {
+                        dash.stop();
+                    }
+                    combo.reset();
+                    spawn |= attack.start(definition);
+                } else if !weapon.is_ready(slot) {
+                    // Fires if the slot is done cooling down before the buffer runs out.
+                    attack.buffer(slot);
+                } else {
+                    let step = combo.next_step(slot, weapon.chain(slot).len());
+                    let charge = weapon
+                        .chain(slot)
+                        .get(step)
+                        .and_then(|definition| definition.charge.clone());
+                    if let Some(charge) = charge {
+                        // The slot's cooldown starts when the charged attack is released.
+                        commands
+                            .entity(entity)
+                            .insert(ChargingBundle::new(slot, step, charge));
+                    } else if let Some(definition) = weapon.try_attack(slot, step, speed) {
+                        combo.start(slot, step, definition.chain_window);
+                        spawn |= attack.start(definition);
+                    }
+                }
+            }
+        }
+
+        if !spawn {
+            continue;
+        }
+        if let Some(definition) = &attack.current {
+            spawn_attack(
+                &mut commands,
+                &image_handles,
+                &mut texture_atlas_layouts,
+                entity,
+                transform,
+                controller.look_direction,
+                definition,
+            );
+        }
+    }
+}
+
+fn spawn_attack(
+    commands: &mut Commands,
+    image_handles: &HandleMap<ImageKey>,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    attacker: Entity,
+    attacker_transform: &Transform,
+    look_direction: Vec2,
+    attack: &AttackDefinition,
+) {
+    // Hitboxes and projectiles are sized in world units, only the sprite is scaled.
+    let attack_transform = Transform {
+        translation: attacker_transform.translation,
+        rotation: Quat::from_rotation_z(Vec2::X.angle_between(look_direction)),
+        ..default()
+    };
+
+    let layout = TextureAtlasLayout::from_grid(
+        UVec2::splat(32),
+        attack.frames as u32,
+        1,
+        Some(UVec2::ZERO),
+        None,
+    );
+    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+    let attack_animation = AttackAnimation::new(attack.frames, attack.frame_interval);
+
+    let mut attack_entity = match (&attack.projectile, &attack.hitbox) {
+        (Some(projectile), _) => {
+            let mut projectile = ProjectileBundle::new(
+                attacker,
+                attack.damage,
+                attack.kind,
+                *projectile,
+                look_direction,
+                GameLayer::PlayerHitbox,
+                GameLayer::Enemies,
+            );
+            if let Some(knockback) = attack.knockback {
+                projectile = projectile.with_knockback(knockback);
+            }
+            let transform = Transform {
+                translation: attack_transform.translation
+                    + (look_direction * attack.reach).extend(0.0),
+                ..attack_transform
+            };
+            commands.spawn((projectile, TransformBundle::from_transform(transform)))
+        }
+        (None, Some(hitbox)) => {
+            let mut damage_zone = DamageZoneBundle::new(
+                attacker,
+                attack.damage,
+                attack.kind,
+                attack.lifetime,
+                hitbox.clone(),
+                GameLayer::PlayerHitbox,
+                GameLayer::Enemies,
+            )
+            .with_melee();
+            if let Some(knockback) = attack.knockback {
+                damage_zone = damage_zone.with_knockback(knockback);
+            }
+            commands.spawn((
+                damage_zone,
+                TransformBundle::from_transform(attack_transform),
+            ))
+        }
+        (None, None) => {
+            warn!("{} has neither a hitbox nor a projectile", attack.name);
+            return;
         }
+    };
+    // Projectiles spawn at their reach already, hitboxes are offset within the attack.
+    let sprite_offset = if attack.projectile.is_some() {
+        0.0
+    } else {
+        attack.reach
+    };
+
+    attack_entity
+        .insert((Name::new(attack.name.clone()), InheritedVisibility::VISIBLE))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: attack.color,
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: (Vec2::X * sprite_offset).extend(10.0),
+                        scale: Vec2::splat(attack.scale).extend(1.0),
+                        ..default()
+                    },
+                    texture: image_handles[&attack.sprite].clone_weak(),
+                    ..default()
+                },
+                TextureAtlas {
+                    layout: texture_atlas_layout,
+                    index: attack_animation.get_atlas_index(),
+                },
+                attack_animation,
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use super::*;
+    use crate::game::{ability::AbilitySlot, damage_zone::DamageZone, weapon::WeaponDefinition};
+
+    #[test]
+    fn stun_mid_charge_cancels_the_charge() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Assets<TextureAtlasLayout>>()
+            .insert_resource(HandleMap::<ImageKey>::from(HashMap::default()))
+            .add_systems(Update, apply_attack);
+
+        let sword = WeaponDefinition::sword();
+        let charge = sword.secondary[0].charge.clone().unwrap();
+        let mut controller = AttackController {
+            look_direction: Vec2::X,
+            held_secondary: true,
+            ..default()
+        };
+        // What the stun suppression does every frame.
+        controller.clear_intent();
+        let attacker = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                controller,
+                WeaponInventory::new([sword]),
+                Attack::new(1.0),
+                Combo::default(),
+                ChargingBundle::new(AttackSlot::Secondary, 0, charge),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world_mut();
+        assert!(world.get::<Charging>(attacker).is_none());
+        assert!(world.get::<Attack>(attacker).unwrap().is_ready());
+        let inventory = world.get::<WeaponInventory>(attacker).unwrap();
+        assert!(inventory
+            .equipped()
+            .unwrap()
+            .is_ready(AttackSlot::Secondary));
+        let mut zones = world.query::<&DamageZone>();
+        assert_eq!(zones.iter(world).count(), 0);
+    }
+
+    #[test]
+    fn press_buffered_before_the_dash_is_not_a_dash_attack() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Assets<TextureAtlasLayout>>()
+            .insert_resource(HandleMap::<ImageKey>::from(HashMap::default()))
+            .add_systems(Update, apply_attack);
+
+        let mut attack = Attack::new(1.0);
+        attack.buffer(AttackSlot::Primary);
+        let mut dash = Dash::new(
+            100.0,
+            Duration::from_millis(200),
+            Duration::from_millis(100),
+            Duration::from_secs(1),
+        );
+        dash.start(500.0, Duration::from_millis(200));
+        let attacker = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                AttackController {
+                    look_direction: Vec2::X,
+                    ..default()
+                },
+                WeaponInventory::new([WeaponDefinition::sword()]),
+                attack,
+                Combo::default(),
+                dash,
+                CancelRules::player(),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        let attack = world.get::<Attack>(attacker).unwrap();
+        assert_eq!(attack.current.as_ref().unwrap().name, "SwordSlash");
+        assert!(world.get::<Dash>(attacker).unwrap().is_dashing());
+    }
+
+    #[test]
+    fn casting_buffers_the_attack() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Assets<TextureAtlasLayout>>()
+            .insert_resource(HandleMap::<ImageKey>::from(HashMap::default()))
+            .add_systems(Update, apply_attack);
+
+        let attacker = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                AttackController {
+                    intent: Some(AttackSlot::Primary),
+                    look_direction: Vec2::X,
+                    ..default()
+                },
+                WeaponInventory::new([WeaponDefinition::sword()]),
+                Attack::new(1.0),
+                Combo::default(),
+                CastingBundle::new(AbilitySlot::Primary, 10.0, Duration::from_secs(1)),
+                CancelRules::player(),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<Casting>(attacker).is_some());
+        let attack = world.get::<Attack>(attacker).unwrap();
+        assert!(attack.is_ready());
+        assert_eq!(
+            attack.buffered.map(|(slot, _)| slot),
+            Some(AttackSlot::Primary)
+        );
     }
 }
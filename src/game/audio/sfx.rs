@@ -32,6 +32,7 @@ pub enum PlaySfx {
     RandomStep,
 }
 
+/// Footsteps don't affect gameplay, so they don't draw from the run's `GameRng`.
 fn random_step() -> SfxKey {
     [SfxKey::Step1, SfxKey::Step2, SfxKey::Step3, SfxKey::Step4]
         .choose(&mut rand::thread_rng())
@@ -0,0 +1,333 @@
+//! The player's bindings: rebinding actions, and keeping the bindings across runs.
+//!
+//! A button action has at most one binding per device, so rebinding it from the keyboard
+//! leaves its gamepad binding alone and the other way around. Stick actions keep their stick,
+//! and the directions of their keyboard dpad are rebound one at a time.
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use super::input::PlayerAction;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(load_bindings());
+
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(
+        Update,
+        save_bindings.run_if(
+            resource_changed::<InputMap<PlayerAction>>
+                .and_then(not(resource_added::<InputMap<PlayerAction>>)),
+        ),
+    );
+}
+
+#[cfg(not(target_family = "wasm"))]
+const BINDINGS_PATH: &str = "bindings.ron";
+
+/// What kind of device an input comes from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    MouseKeyboard,
+    /// Buttons, sticks and triggers.
+    Gamepad,
+}
+
+impl InputDevice {
+    pub fn of(input: &InputKind) -> Self {
+        match input {
+            InputKind::GamepadButton(_) | InputKind::SingleAxis(_) | InputKind::DualAxis(_) => {
+                InputDevice::Gamepad
+            }
+            _ => InputDevice::MouseKeyboard,
+        }
+    }
+
+    fn of_binding(binding: &UserInput) -> Option<Self> {
+        match binding {
+            UserInput::Single(input) => Some(Self::of(input)),
+            UserInput::Chord(inputs) => inputs.first().map(Self::of),
+            UserInput::VirtualDPad(dpad) => Some(Self::of(&dpad.up)),
+            UserInput::VirtualAxis(axis) => Some(Self::of(&axis.negative)),
+        }
+    }
+}
+
+/// The input is already bound to another action.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BindingConflict(pub PlayerAction);
+
+/// Binds `input` to `action` in place of the action's binding on the same device.
+///
+/// Fails without changing anything if another action already uses `input`.
+pub fn rebind(
+    input_map: &mut InputMap<PlayerAction>,
+    action: PlayerAction,
+    input: InputKind,
+) -> Result<(), BindingConflict> {
+    if let Some(other) = bound_action(input_map, &input).filter(|&other| other != action) {
+        return Err(BindingConflict(other));
+    }
+
+    let device = InputDevice::of(&input);
+    let kept: Vec<UserInput> = input_map
+        .get(&action)
+        .into_iter()
+        .flatten()
+        .filter(|binding| InputDevice::of_binding(binding) != Some(device))
+        .cloned()
+        .collect();
+    input_map.clear_action(&action);
+    input_map.insert_one_to_many(action, kept);
+    input_map.insert(action, input);
+    Ok(())
+}
+
+/// One of the directions of a virtual dpad.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DPadDirection {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+impl DPadDirection {
+    /// The direction to rebind after this one, going around the dpad.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            DPadDirection::Up => Some(DPadDirection::Left),
+            DPadDirection::Left => Some(DPadDirection::Down),
+            DPadDirection::Down => Some(DPadDirection::Right),
+            DPadDirection::Right => None,
+        }
+    }
+
+    fn input_mut(self, dpad: &mut VirtualDPad) -> &mut InputKind {
+        match self {
+            DPadDirection::Up => &mut dpad.up,
+            DPadDirection::Left => &mut dpad.left,
+            DPadDirection::Down => &mut dpad.down,
+            DPadDirection::Right => &mut dpad.right,
+        }
+    }
+}
+
+/// Binds `input` to one direction of the action's keyboard dpad, e.g. to move with ZQSD.
+/// An action without one starts from its default dpad.
+///
+/// Fails without changing anything if another action or direction already uses `input`.
+pub fn rebind_direction(
+    input_map: &mut InputMap<PlayerAction>,
+    action: PlayerAction,
+    direction: DPadDirection,
+    input: InputKind,
+) -> Result<(), BindingConflict> {
+    let Some(mut dpad) = keyboard_dpad(input_map, action)
+        .or_else(|| keyboard_dpad(&PlayerAction::default_input_map(), action))
+    else {
+        warn!("{action:?} has no directions to rebind");
+        return Ok(());
+    };
+    if *direction.input_mut(&mut dpad) == input {
+        return Ok(());
+    }
+    if let Some(other) = bound_action(input_map, &input) {
+        return Err(BindingConflict(other));
+    }
+
+    *direction.input_mut(&mut dpad) = input;
+    let kept: Vec<UserInput> = input_map
+        .get(&action)
+        .into_iter()
+        .flatten()
+        .filter(|binding| !is_keyboard_dpad(binding))
+        .cloned()
+        .collect();
+    input_map.clear_action(&action);
+    input_map.insert_one_to_many(action, kept);
+    input_map.insert(action, dpad);
+    Ok(())
+}
+
+fn keyboard_dpad(input_map: &InputMap<PlayerAction>, action: PlayerAction) -> Option<VirtualDPad> {
+    input_map
+        .get(&action)?
+        .iter()
+        .find(|binding| is_keyboard_dpad(binding))
+        .and_then(|binding| match binding {
+            UserInput::VirtualDPad(dpad) => Some(dpad.clone()),
+            _ => None,
+        })
+}
+
+fn is_keyboard_dpad(binding: &UserInput) -> bool {
+    matches!(binding, UserInput::VirtualDPad(_))
+        && InputDevice::of_binding(binding) == Some(InputDevice::MouseKeyboard)
+}
+
+/// The action `input` is bound to, alone or as part of a chord or virtual dpad.
+pub fn bound_action(input_map: &InputMap<PlayerAction>, input: &InputKind) -> Option<PlayerAction> {
+    input_map
+        .iter()
+        .find(|(_, bindings)| bindings.iter().any(|binding| uses(binding, input)))
+        .map(|(action, _)| *action)
+}
+
+fn uses(binding: &UserInput, input: &InputKind) -> bool {
+    match binding {
+        UserInput::Single(single) => single == input,
+        UserInput::Chord(inputs) => inputs.contains(input),
+        UserInput::VirtualDPad(dpad) => [dpad.up, dpad.down, dpad.left, dpad.right].contains(input),
+        UserInput::VirtualAxis(axis) => [axis.negative, axis.positive].contains(input),
+    }
+}
+
+/// Reads the bindings saved by a previous run, or falls back to the defaults.
+#[cfg(not(target_family = "wasm"))]
+fn load_bindings() -> InputMap<PlayerAction> {
+    let mut input_map: InputMap<PlayerAction> = match std::fs::read_to_string(BINDINGS_PATH) {
+        Ok(saved) => match ron::from_str(&saved) {
+            Ok(input_map) => input_map,
+            Err(error) => {
+                warn!("Could not parse {BINDINGS_PATH}, using the default bindings: {error}");
+                return PlayerAction::default_input_map();
+            }
+        },
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return PlayerAction::default_input_map();
+        }
+        Err(error) => {
+            warn!("Could not read {BINDINGS_PATH}, using the default bindings: {error}");
+            return PlayerAction::default_input_map();
+        }
+    };
+
+    // Actions added since the bindings were saved get their default bindings.
+    let defaults = PlayerAction::default_input_map();
+    for action in PlayerAction::ALL {
+        if input_map.get(&action).is_none() {
+            if let Some(bindings) = defaults.get(&action) {
+                input_map.insert_one_to_many(action, bindings.iter().cloned());
+            }
+        }
+    }
+    info!("Loaded bindings from {BINDINGS_PATH}");
+    input_map
+}
+
+/// Bindings are not kept on the web.
+#[cfg(target_family = "wasm")]
+fn load_bindings() -> InputMap<PlayerAction> {
+    PlayerAction::default_input_map()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_bindings(input_map: Res<InputMap<PlayerAction>>) {
+    let result = ron::ser::to_string_pretty(&*input_map, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|saved| std::fs::write(BINDINGS_PATH, saved).map_err(|error| error.to_string()));
+    match result {
+        Ok(()) => info!("Saved bindings to {BINDINGS_PATH}"),
+        Err(error) => warn!("Could not save {BINDINGS_PATH}: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key_code: KeyCode) -> InputKind {
+        InputKind::PhysicalKey(key_code)
+    }
+
+    #[test]
+    fn bound_action_finds_inputs_inside_dpads_and_chords() {
+        let mut input_map = PlayerAction::default_input_map();
+        input_map.insert_chord(
+            PlayerAction::Interact,
+            [KeyCode::ControlLeft, KeyCode::KeyX],
+        );
+        assert_eq!(
+            bound_action(&input_map, &key(KeyCode::KeyA)),
+            Some(PlayerAction::Move)
+        );
+        assert_eq!(
+            bound_action(&input_map, &key(KeyCode::ArrowUp)),
+            Some(PlayerAction::Look)
+        );
+        assert_eq!(
+            bound_action(&input_map, &key(KeyCode::KeyX)),
+            Some(PlayerAction::Interact)
+        );
+        assert_eq!(bound_action(&input_map, &key(KeyCode::KeyZ)), None);
+    }
+
+    #[test]
+    fn rebind_replaces_the_binding_of_the_same_device() {
+        let mut input_map = PlayerAction::default_input_map();
+        rebind(&mut input_map, PlayerAction::Dash, key(KeyCode::KeyC)).unwrap();
+        let bindings = input_map.get(&PlayerAction::Dash).unwrap();
+        assert!(bindings.contains(&UserInput::Single(key(KeyCode::KeyC))));
+        assert!(!bindings.contains(&UserInput::Single(key(KeyCode::Space))));
+        assert!(
+            bindings.contains(&UserInput::Single(InputKind::GamepadButton(
+                GamepadButtonType::South
+            )))
+        );
+        assert_eq!(bindings.len(), 2);
+    }
+
+    #[test]
+    fn rebind_to_a_used_input_fails_without_changes() {
+        let mut input_map = PlayerAction::default_input_map();
+        let result = rebind(&mut input_map, PlayerAction::Dash, key(KeyCode::KeyW));
+        assert_eq!(result, Err(BindingConflict(PlayerAction::Move)));
+        assert_eq!(
+            bound_action(&input_map, &key(KeyCode::Space)),
+            Some(PlayerAction::Dash)
+        );
+        // Rebinding an action to its own input changes nothing.
+        rebind(&mut input_map, PlayerAction::Dash, key(KeyCode::Space)).unwrap();
+        assert_eq!(input_map.get(&PlayerAction::Dash).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rebind_direction_only_changes_that_direction() {
+        let mut input_map = PlayerAction::default_input_map();
+        rebind_direction(
+            &mut input_map,
+            PlayerAction::Move,
+            DPadDirection::Up,
+            key(KeyCode::KeyZ),
+        )
+        .unwrap();
+        let dpad = keyboard_dpad(&input_map, PlayerAction::Move).unwrap();
+        assert_eq!(dpad.up, key(KeyCode::KeyZ));
+        assert_eq!(dpad.left, key(KeyCode::KeyA));
+        assert_eq!(bound_action(&input_map, &key(KeyCode::KeyW)), None);
+        // The stick is kept.
+        assert_eq!(input_map.get(&PlayerAction::Move).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rebind_direction_to_a_used_input_fails_without_changes() {
+        let mut input_map = PlayerAction::default_input_map();
+        let result = rebind_direction(
+            &mut input_map,
+            PlayerAction::Move,
+            DPadDirection::Left,
+            key(KeyCode::KeyD),
+        );
+        assert_eq!(result, Err(BindingConflict(PlayerAction::Move)));
+        let result = rebind_direction(
+            &mut input_map,
+            PlayerAction::Move,
+            DPadDirection::Left,
+            key(KeyCode::KeyE),
+        );
+        assert_eq!(result, Err(BindingConflict(PlayerAction::Interact)));
+        let dpad = keyboard_dpad(&input_map, PlayerAction::Move).unwrap();
+        assert_eq!(dpad.left, key(KeyCode::KeyA));
+    }
+}
@@ -0,0 +1,158 @@
+//! Raise a guard to soften hits from the front, or time it to parry them.
+//!
+//! A parry negates the hit, staggers melee attackers and reflects projectiles.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use super::{
+    attack::AttackController, death::Dying, input::PlayerAction, knockback::Knockback,
+    spawn::player::Player,
+};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<BlockEvent>();
+    app.register_type::<BlockController>();
+    app.register_type::<Block>();
+    app.add_systems(
+        Update,
+        (
+            tick_blocks.in_set(AppSet::TickTimers),
+            record_block_controller.in_set(AppSet::RecordInput),
+            suppress_attacks_while_blocking.in_set(AppSet::ConstrainInput),
+            apply_block.in_set(AppSet::Update),
+        ),
+    );
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct BlockController {
+    /// Whether the block button is held down.
+    pub intent: bool,
+}
+
+fn record_block_controller(
+    action_state: Res<ActionState<PlayerAction>>,
+    mut query: Query<&mut BlockController, With<Player>>,
+) {
+    for mut block_controller in &mut query {
+        block_controller.intent = action_state.pressed(&PlayerAction::Block);
+    }
+}
+
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub enum BlockState {
+    #[default]
+    Lowered,
+    /// Raised for this long.
+    Raised(Duration),
+    OnCooldown(Duration),
+}
+
+/// How a hit was stopped by a raised guard.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Guard {
+    Blocked,
+    Parried,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Block {
+    pub state: BlockState,
+    /// Where the guard points. Follows the look direction.
+    pub facing: Vec2,
+    /// Angle in front of the entity that is covered, in radians.
+    pub arc: f32,
+    /// Applied to the damage of blocked hits.
+    pub damage_multiplier: f32,
+    /// Time after raising the guard during which hits are parried.
+    pub parry_window: Duration,
+    /// Applied to the entity's movement speed while the guard is raised.
+    pub move_speed_multiplier: f32,
+    /// Time after lowering the guard before it can be raised again.
+    pub cooldown: Duration,
+    /// Knockback dealt to melee attackers whose hit was parried.
+    pub stagger: Knockback,
+}
+
+impl Block {
+    pub fn is_raised(&self) -> bool {
+        matches!(self.state, BlockState::Raised(_))
+    }
+
+    pub fn move_speed_multiplier(&self) -> f32 {
+        if self.is_raised() {
+            self.move_speed_multiplier
+        } else {
+            1.0
+        }
+    }
+
+    /// How a hit coming from `direction` is stopped, if it is.
+    pub fn guard(&self, direction: Vec2) -> Option<Guard> {
+        let BlockState::Raised(held) = self.state else {
+            return None;
+        };
+        if self.facing.angle_between(direction).abs() > self.arc / 2.0 {
+            return None;
+        }
+        if held < self.parry_window {
+            Some(Guard::Parried)
+        } else {
+            Some(Guard::Blocked)
+        }
+    }
+}
+
+/// Sent when a hit is stopped by a raised guard.
+#[derive(Event, Debug)]
+pub struct BlockEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub guard: Guard,
+}
+
+fn tick_blocks(time: Res<Time>, mut query: Query<&mut Block>) {
+    for mut block in &mut query {
+        block.state = match block.state {
+            BlockState::Raised(held) => BlockState::Raised(held + time.delta()),
+            BlockState::OnCooldown(remaining) => {
+                let remaining = remaining.saturating_sub(time.delta());
+                if remaining.is_zero() {
+                    BlockState::Lowered
+                } else {
+                    BlockState::OnCooldown(remaining)
+                }
+            }
+            BlockState::Lowered => BlockState::Lowered,
+        };
+    }
+}
+
+fn suppress_attacks_while_blocking(mut query: Query<(&Block, &mut AttackController)>) {
+    for (block, mut attack_controller) in &mut query {
+        if block.is_raised() {
+            attack_controller.clear_intent();
+        }
+    }
+}
+
+fn apply_block(
+    mut query: Query<(&BlockController, &mut Block, Option<&AttackController>), Without<Dying>>,
+) {
+    for (controller, mut block, attack_controller) in &mut query {
+        if let Some(attack_controller) = attack_controller {
+            block.facing = attack_controller.look_direction;
+        }
+        block.state = match block.state {
+            BlockState::Lowered if controller.intent => BlockState::Raised(Duration::ZERO),
+            BlockState::Raised(_) if !controller.intent => BlockState::OnCooldown(block.cooldown),
+            state => state,
+        };
+    }
+}
@@ -0,0 +1,48 @@
+//! Which player actions can interrupt which others.
+//!
+//! Without a rule, an action pressed while busy is buffered until the entity is free.
+
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CancelRules>();
+}
+
+/// What an entity is busy doing, as far as cancelling goes.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BusyState {
+    AttackWindup,
+    AttackActive,
+    AttackRecovery,
+    Dashing,
+    DashLanding,
+    Casting,
+}
+
+/// An action that can interrupt a [`BusyState`].
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CancelAction {
+    /// Attacking out of a dash turns into the weapon's dash attack.
+    Attack,
+    Dash,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct CancelRules(pub Vec<(BusyState, CancelAction)>);
+
+impl CancelRules {
+    /// Dash out of attack recovery and casts, attack out of dashes.
+    pub fn player() -> Self {
+        CancelRules(vec![
+            (BusyState::AttackRecovery, CancelAction::Dash),
+            (BusyState::Casting, CancelAction::Dash),
+            (BusyState::Dashing, CancelAction::Attack),
+            (BusyState::DashLanding, CancelAction::Attack),
+        ])
+    }
+
+    pub fn allows(&self, state: BusyState, action: CancelAction) -> bool {
+        self.0.contains(&(state, action))
+    }
+}
@@ -0,0 +1,144 @@
+//! Hold an attack button to charge a heavier attack, release it to strike.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    health::DamageDealtEvent,
+    ui::{
+        percentage::{AsPercentage, Percentage},
+        status_bar::definition::{Size, StatusBarDefinition},
+    },
+    weapon::{AttackDefinition, AttackSlot},
+};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Charging>();
+    app.add_systems(
+        Update,
+        (
+            tick_charging.in_set(AppSet::TickTimers),
+            cancel_charging_on_hit.in_set(AppSet::Update),
+        ),
+    );
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct ChargeStage {
+    /// How long the button has to be held to reach this stage.
+    pub hold: Duration,
+    pub damage_multiplier: f32,
+    pub reach_multiplier: f32,
+    pub knockback_multiplier: f32,
+}
+
+/// Makes an attack charged: it fires on release instead of on press.
+#[derive(Reflect, Clone, Debug)]
+pub struct ChargeDefinition {
+    /// Ordered by `hold`. Releasing before the first stage fires the attack unscaled.
+    pub stages: Vec<ChargeStage>,
+    /// Applied to the attacker's movement speed while charging.
+    pub move_speed_multiplier: f32,
+}
+
+impl ChargeDefinition {
+    fn stage(&self, held: Duration) -> Option<&ChargeStage> {
+        self.stages.iter().rev().find(|stage| held >= stage.hold)
+    }
+
+    fn full_charge(&self) -> Duration {
+        self.stages
+            .last()
+            .map_or(Duration::ZERO, |stage| stage.hold)
+    }
+}
+
+/// An attacker holding a charged attack.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Charging {
+    pub slot: AttackSlot,
+    /// The combo step that is being charged.
+    pub step: usize,
+    pub charge: ChargeDefinition,
+    pub held: Duration,
+}
+
+impl AsPercentage for Charging {
+    fn percentage(&self) -> Percentage {
+        let full_charge = self.charge.full_charge();
+        if full_charge.is_zero() {
+            return Percentage::new(1.0);
+        }
+        Percentage::new(self.held.as_secs_f32() / full_charge.as_secs_f32())
+    }
+}
+
+impl Charging {
+    pub fn move_speed_multiplier(&self) -> f32 {
+        self.charge.move_speed_multiplier
+    }
+
+    /// The attack, scaled by the charge stage reached so far.
+    pub fn charged(&self, attack: &AttackDefinition) -> AttackDefinition {
+        let mut attack = attack.clone();
+        if let Some(stage) = self.charge.stage(self.held) {
+            attack.damage *= stage.damage_multiplier;
+            attack.reach *= stage.reach_multiplier;
+            if let Some(hitbox) = &mut attack.hitbox {
+                hitbox.scale(stage.reach_multiplier);
+            }
+            if let Some(knockback) = &mut attack.knockback {
+                knockback.strength *= stage.knockback_multiplier;
+            }
+        }
+        attack
+    }
+}
+
+/// The charge and its meter, inserted and removed together.
+#[derive(Bundle)]
+pub struct ChargingBundle {
+    pub charging: Charging,
+    pub charge_bar: StatusBarDefinition<Charging>,
+}
+
+impl ChargingBundle {
+    pub fn new(slot: AttackSlot, step: usize, charge: ChargeDefinition) -> Self {
+        ChargingBundle {
+            charging: Charging {
+                slot,
+                step,
+                charge,
+                held: Duration::ZERO,
+            },
+            charge_bar: StatusBarDefinition {
+                size: Size::new(40.0, 3.0),
+                offset: Vec3::new(0.0, 34.0, 10.0),
+                foreground_color: Color::srgb(1.0, 0.85, 0.1),
+                ..default()
+            },
+        }
+    }
+}
+
+fn tick_charging(time: Res<Time>, mut query: Query<&mut Charging>) {
+    for mut charging in &mut query {
+        charging.held += time.delta();
+    }
+}
+
+/// Getting hit drops the charge. Damage over time doesn't count as a hit.
+fn cancel_charging_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<DamageDealtEvent>,
+    query: Query<(), With<Charging>>,
+) {
+    for event in events.read() {
+        if !event.damage_over_time && query.contains(event.target) {
+            commands.entity(event.target).remove::<ChargingBundle>();
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Record every resolved hit of a run, for debugging and balancing.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::health::{DamageDealtEvent, DamageKind};
+use crate::screen::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CombatLog>();
+    app.register_type::<CombatLog>();
+    app.add_systems(OnEnter(Screen::Playing), clear_combat_log);
+    app.add_systems(OnExit(Screen::Playing), log_damage_breakdown);
+    app.add_systems(Update, record_hits.run_if(in_state(Screen::Playing)));
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct CombatLogEntry {
+    /// Time since the start of the run.
+    pub time: Duration,
+    pub source: Entity,
+    /// Kept as text since the source may be despawned by the time the log is read.
+    pub source_name: String,
+    /// What the source hit with, or the source's name if it hit with itself.
+    pub attack_name: String,
+    pub target: Entity,
+    pub target_name: String,
+    pub amount: f32,
+    pub kind: DamageKind,
+    pub killing_blow: bool,
+}
+
+/// Totals for one attack over a run.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct AttackBreakdown {
+    pub damage: f32,
+    pub hits: u32,
+    pub kills: u32,
+}
+
+/// Every hit resolved during the current run.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct CombatLog {
+    run_start: Duration,
+    entries: Vec<CombatLogEntry>,
+}
+
+impl CombatLog {
+    pub fn entries(&self) -> &[CombatLogEntry] {
+        &self.entries
+    }
+
+    /// Damage, hit and kill totals per attack name, e.g. per weapon attack of the player.
+    pub fn breakdown(&self) -> HashMap<&str, AttackBreakdown> {
+        let mut breakdown: HashMap<&str, AttackBreakdown> = HashMap::default();
+        for entry in &self.entries {
+            let totals = breakdown.entry(&entry.attack_name).or_default();
+            totals.damage += entry.amount;
+            totals.hits += 1;
+            totals.kills += u32::from(entry.killing_blow);
+        }
+        breakdown
+    }
+}
+
+fn clear_combat_log(time: Res<Time>, mut combat_log: ResMut<CombatLog>) {
+    combat_log.run_start = time.elapsed();
+    combat_log.entries.clear();
+}
+
+fn record_hits(
+    time: Res<Time>,
+    mut events: EventReader<DamageDealtEvent>,
+    mut combat_log: ResMut<CombatLog>,
+    name_query: Query<&Name>,
+) {
+    let name = |entity: Entity| {
+        name_query
+            .get(entity)
+            .map_or_else(|_| format!("{entity:?}"), |name| format!("{name}"))
+    };
+    for event in events.read() {
+        let source_name = name(event.source);
+        let entry = CombatLogEntry {
+            time: time.elapsed().saturating_sub(combat_log.run_start),
+            source: event.source,
+            attack_name: event
+                .attack
+                .as_ref()
+                .map_or_else(|| source_name.clone(), |attack| attack.to_string()),
+            source_name,
+            target: event.target,
+            target_name: name(event.target),
+            amount: event.amount,
+            kind: event.kind,
+            killing_blow: event.killing_blow,
+        };
+        combat_log.entries.push(entry);
+    }
+}
+
+fn log_damage_breakdown(combat_log: Res<CombatLog>) {
+    let mut breakdown: Vec<_> = combat_log.breakdown().into_iter().collect();
+    breakdown.sort_by(|(_, a), (_, b)| b.damage.total_cmp(&a.damage));
+    info!(
+        "Damage breakdown for the run ({} hits):",
+        combat_log.entries().len()
+    );
+    for (attack, totals) in breakdown {
+        info!(
+            "  {attack}: {:.1} damage in {} hits, {} kills",
+            totals.damage, totals.hits, totals.kills
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Chain attacks into combos by pressing again within a timing window.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{attack::Attack, health::DamageDealtEvent, weapon::AttackSlot};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Combo>();
+    app.add_systems(
+        Update,
+        (
+            tick_combos.in_set(AppSet::TickTimers),
+            reset_combos_on_hit.in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// Tracks where an attacker is in its current combo chain.
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct Combo {
+    /// The slot and step of the last attack, while the chain is still going.
+    last: Option<(AttackSlot, usize)>,
+    window: Duration,
+}
+
+impl Combo {
+    /// The step to use for an attack of `slot`, whose chain has `len` steps.
+    /// Chains restart after their last step or when switching slots.
+    pub fn next_step(&self, slot: AttackSlot, len: usize) -> usize {
+        match self.last {
+            Some((last_slot, step)) if last_slot == slot && step + 1 < len => step + 1,
+            _ => 0,
+        }
+    }
+
+    /// Record an attack. Its chain window only starts running once the attacker is ready.
+    pub fn start(&mut self, slot: AttackSlot, step: usize, chain_window: Duration) {
+        self.last = Some((slot, step));
+        self.window = chain_window;
+    }
+
+    /// Drop the chain, so the next attack starts from the first step.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.window = Duration::ZERO;
+    }
+
+    fn apply_delta_time(&mut self, delta: Duration) {
+        self.window = self.window.saturating_sub(delta);
+        if self.window.is_zero() {
+            self.last = None;
+        }
+    }
+}
+
+fn tick_combos(time: Res<Time>, mut query: Query<(&mut Combo, Option<&Attack>)>) {
+    for (mut combo, attack) in &mut query {
+        if attack.is_some_and(|attack| !attack.is_ready()) {
+            continue;
+        }
+        combo.apply_delta_time(time.delta());
+    }
+}
+
+/// Getting hit breaks the chain. Damage over time doesn't count as a hit.
+fn reset_combos_on_hit(mut events: EventReader<DamageDealtEvent>, mut query: Query<&mut Combo>) {
+    for event in events.read() {
+        if event.damage_over_time {
+            continue;
+        }
+        if let Ok(mut combo) = query.get_mut(event.target) {
+            combo.reset();
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Modifiers applied between a hit happening and health being reduced.
+//!
+//! The order is fixed:
+//! 1. attacker flat bonus, once per hit rather than on every damage over time tick,
+//! 2. attacker percent bonus,
+//! 3. critical hit roll,
+//! 4. defender [`Resistances`] for the hit's kind,
+//! 5. defender armor,
+//! 6. defender damage taken multiplier, including the reduction of a blocked hit.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::health::{DamageKind, Resistances};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DamageDealtModifiers>();
+    app.register_type::<DamageTakenModifiers>();
+}
+
+/// Attacker-side modifiers to the damage of every hit an entity deals.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct DamageDealtModifiers {
+    /// Added to the base damage.
+    pub flat: f32,
+    /// Added to the damage as a fraction, e.g. `0.2` for +20%.
+    pub percent: f32,
+    /// Chance for a hit to be critical, between 0 and 1.
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+}
+
+impl Default for DamageDealtModifiers {
+    fn default() -> Self {
+        DamageDealtModifiers {
+            flat: 0.0,
+            percent: 0.0,
+            crit_chance: 0.0,
+            crit_multiplier: 1.5,
+        }
+    }
+}
+
+/// Defender-side modifiers to the damage of every hit an entity takes.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct DamageTakenModifiers {
+    /// Subtracted from each hit, after resistances.
+    pub armor: f32,
+    /// Applied last. A blocked hit also gets the block's damage multiplier here.
+    pub multiplier: f32,
+}
+
+impl Default for DamageTakenModifiers {
+    fn default() -> Self {
+        DamageTakenModifiers {
+            armor: 0.0,
+            multiplier: 1.0,
+        }
+    }
+}
+
+/// The outcome of running a hit through the modifiers.
+pub struct ModifiedDamage {
+    pub amount: f32,
+    pub critical: bool,
+}
+
+/// Run a hit through the attacker's and defender's modifiers.
+/// Damage over time doesn't get the flat bonus and can't be critical.
+/// The critical hit roll draws from `rng`.
+pub fn modify_damage(
+    damage: f32,
+    kind: DamageKind,
+    damage_over_time: bool,
+    dealt: Option<&DamageDealtModifiers>,
+    resistances: Option<&Resistances>,
+    taken: Option<&DamageTakenModifiers>,
+    rng: &mut impl Rng,
+) -> ModifiedDamage {
+    let mut amount = damage;
+    let mut critical = false;
+
+    if let Some(dealt) = dealt {
+        if !damage_over_time {
+            amount += dealt.flat;
+        }
+        amount *= 1.0 + dealt.percent;
+        if !damage_over_time && rng.gen::<f32>() < dealt.crit_chance {
+            amount *= dealt.crit_multiplier;
+            critical = true;
+        }
+    }
+    if let Some(resistances) = resistances {
+        amount = resistances.mitigate(amount, kind);
+    }
+    if let Some(taken) = taken {
+        amount = (amount - taken.armor) * taken.multiplier;
+    }
+
+    ModifiedDamage {
+        amount: amount.max(0.0),
+        critical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn dealt(crit_chance: f32) -> DamageDealtModifiers {
+        DamageDealtModifiers {
+            flat: 2.0,
+            percent: 0.5,
+            crit_chance,
+            crit_multiplier: 2.0,
+        }
+    }
+
+    #[test]
+    fn modifiers_apply_in_order() {
+        let resistances = Resistances {
+            physical: 0.5,
+            ..default()
+        };
+        let taken = DamageTakenModifiers {
+            armor: 1.0,
+            multiplier: 2.0,
+        };
+        let modified = modify_damage(
+            10.0,
+            DamageKind::Physical,
+            false,
+            Some(&dealt(1.0)),
+            Some(&resistances),
+            Some(&taken),
+            &mut StdRng::seed_from_u64(0),
+        );
+        // ((10 + 2) * 1.5 * 2 * 0.5 - 1) * 2
+        assert_eq!(modified.amount, 34.0);
+        assert!(modified.critical);
+    }
+
+    #[test]
+    fn resistances_only_apply_to_their_kind() {
+        let resistances = Resistances {
+            fire: 0.5,
+            ..default()
+        };
+        let modified = modify_damage(
+            10.0,
+            DamageKind::Physical,
+            false,
+            None,
+            Some(&resistances),
+            None,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(modified.amount, 10.0);
+    }
+
+    #[test]
+    fn damage_over_time_skips_flat_bonus_and_crits() {
+        let modified = modify_damage(
+            10.0,
+            DamageKind::Fire,
+            true,
+            Some(&dealt(1.0)),
+            None,
+            None,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(modified.amount, 15.0);
+        assert!(!modified.critical);
+    }
+
+    #[test]
+    fn blocking_reduces_the_hit_after_the_attackers_modifiers() {
+        // What a block with a 0.5 damage multiplier does to a defender without modifiers.
+        let blocked = DamageTakenModifiers {
+            multiplier: 0.5,
+            ..default()
+        };
+        let modified = modify_damage(
+            10.0,
+            DamageKind::Physical,
+            false,
+            Some(&dealt(1.0)),
+            None,
+            Some(&blocked),
+            &mut StdRng::seed_from_u64(0),
+        );
+        // (10 + 2) * 1.5 * 2 * 0.5
+        assert_eq!(modified.amount, 18.0);
+    }
+
+    #[test]
+    fn damage_never_goes_negative() {
+        let taken = DamageTakenModifiers {
+            armor: 100.0,
+            multiplier: 1.0,
+        };
+        let modified = modify_damage(
+            10.0,
+            DamageKind::Physical,
+            false,
+            Some(&dealt(0.0)),
+            None,
+            Some(&taken),
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(modified.amount, 0.0);
+        assert!(!modified.critical);
+    }
+}
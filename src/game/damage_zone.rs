@@ -1,12 +1,19 @@
-use std::{collections::HashSet, time::Duration};
+use std::time::Duration;
 
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use super::health::{DamageEvent, Health};
+use super::{
+    health::{DamageEvent, DamageKind, Health},
+    hitbox::Hitbox,
+    knockback::Knockback,
+    status_effect::InflictsStatusEffect,
+};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Update, handle_damage_zones);
+    app.add_event::<DamageZoneEntered>();
+    app.add_event::<DamageZoneExited>();
+    app.add_systems(Update, (handle_damage_zones, send_damage_zone_events));
 }
 
 #[derive(Component, Reflect)]
@@ -14,15 +21,49 @@ pub(super) fn plugin(app: &mut App) {
 pub struct DamageZone {
     emitter: Entity,
     damage: f32,
-    lifetime: Duration,
+    kind: DamageKind,
+    knockback: Option<Knockback>,
+    hitbox: Hitbox,
+    /// Time between hits on the same entity. Without it, each entity is hit once.
+    tick_interval: Option<Duration>,
+    /// Whether the hits count as damage over time rather than as separate hits.
+    damage_over_time: bool,
+    /// Whether the emitter strikes in person with the zone, e.g. a sword swing.
+    melee: bool,
+    duration: Duration,
+    /// Time left before despawning, or `None` to never despawn.
+    lifetime: Option<Duration>,
 }
 
-/// A component that stores the entities that were damaged by damage zone already.
+/// When each entity was last hit by the zone, in elapsed game time.
 #[derive(Reflect, Clone, Component, Debug, Default, Deref, DerefMut, PartialEq, Eq)]
-#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
-#[cfg_attr(feature = "serialize", reflect(Serialize, Deserialize))]
 #[reflect(Debug, Component, Default, PartialEq)]
-pub struct DamagedEntities(pub HashSet<Entity>);
+pub struct DamagedEntities(pub HashMap<Entity, Duration>);
+
+impl DamagedEntities {
+    /// Whether `entity` can be hit `now`, given the time between hits.
+    pub fn can_hit(&self, entity: Entity, now: Duration, interval: Option<Duration>) -> bool {
+        match (self.0.get(&entity), interval) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(&last_hit), Some(interval)) => now.saturating_sub(last_hit) >= interval,
+        }
+    }
+}
+
+/// Sent when an entity with health starts overlapping a damage zone.
+#[derive(Event, Debug)]
+pub struct DamageZoneEntered {
+    pub zone: Entity,
+    pub entity: Entity,
+}
+
+/// Sent when an entity with health stops overlapping a damage zone.
+#[derive(Event, Debug)]
+pub struct DamageZoneExited {
+    pub zone: Entity,
+    pub entity: Entity,
+}
 
 #[derive(Bundle)]
 pub struct DamageZoneBundle {
@@ -37,27 +78,60 @@ impl DamageZoneBundle {
     pub fn new(
         emitter: Entity,
         damage: f32,
+        kind: DamageKind,
         lifetime: Duration,
-        circle_radius: f32,
+        hitbox: Hitbox,
         own_layer: impl Into<LayerMask>,
         collides_with_layers: impl Into<LayerMask>,
     ) -> DamageZoneBundle {
         DamageZoneBundle {
+            collider: hitbox.collider(0.0),
             damage_zone: DamageZone {
                 emitter,
                 damage,
-                lifetime,
+                kind,
+                knockback: None,
+                hitbox,
+                tick_interval: None,
+                damage_over_time: false,
+                melee: false,
+                duration: lifetime,
+                lifetime: Some(lifetime),
             },
-            collider: Collider::compound(vec![(
-                Vec2::X * 50.0,
-                0.0,
-                Collider::round_rectangle(circle_radius, circle_radius, 8.0),
-            )]),
             layers: CollisionLayers::new(own_layer, collides_with_layers),
             sensor: Sensor,
-            damaged_entities: DamagedEntities(HashSet::new()),
+            damaged_entities: DamagedEntities::default(),
         }
     }
+
+    pub fn with_knockback(mut self, knockback: Knockback) -> Self {
+        self.damage_zone.knockback = Some(knockback);
+        self
+    }
+
+    /// Hit everything inside the zone every `tick_interval`, instead of once.
+    pub fn with_tick_interval(mut self, tick_interval: Duration) -> Self {
+        self.damage_zone.tick_interval = Some(tick_interval);
+        self
+    }
+
+    /// Make the zone's hits count as damage over time, e.g. for a lingering cloud.
+    pub fn with_damage_over_time(mut self) -> Self {
+        self.damage_zone.damage_over_time = true;
+        self
+    }
+
+    /// Make the zone a melee strike of its emitter, which parries stagger.
+    pub fn with_melee(mut self) -> Self {
+        self.damage_zone.melee = true;
+        self
+    }
+
+    /// Keep the zone until it is despawned by something else.
+    pub fn with_infinite_lifetime(mut self) -> Self {
+        self.damage_zone.lifetime = None;
+        self
+    }
 }
 
 fn handle_damage_zones(
@@ -66,36 +140,124 @@ fn handle_damage_zones(
     time: Res<Time>,
     mut query: Query<(
         Entity,
+        &GlobalTransform,
         &CollidingEntities,
         &mut DamageZone,
         &mut DamagedEntities,
+        &mut Collider,
+        Option<&InflictsStatusEffect>,
+        Option<&Name>,
     )>,
-    damageable_query: Query<Entity, With<Health>>,
+    collider_parents: Query<&ColliderParent>,
+    damageable_query: Query<(), With<Health>>,
+    transform_query: Query<&GlobalTransform>,
 ) {
-    for (damage_zone_entity, colliding_entities, mut damage_zone, mut damaged_entities) in
-        &mut query
+    for (
+        damage_zone_entity,
+        global_transform,
+        colliding_entities,
+        mut damage_zone,
+        mut damaged_entities,
+        mut collider,
+        inflicts_status_effect,
+        name,
+    ) in &mut query
     {
-        damage_zone.lifetime = damage_zone.lifetime.saturating_sub(time.delta());
-        if damage_zone.lifetime <= Duration::ZERO {
-            commands.entity(damage_zone_entity).despawn_recursive();
-            continue;
-        }
-
-        for &colliding_entity in colliding_entities.iter() {
-            if damaged_entities.0.contains(&colliding_entity) {
+        if let Some(lifetime) = &mut damage_zone.lifetime {
+            *lifetime = lifetime.saturating_sub(time.delta());
+            if lifetime.is_zero() {
+                commands.entity(damage_zone_entity).despawn_recursive();
                 continue;
             }
+        }
+        if damage_zone.hitbox.is_sweeping() {
+            let progress = damage_zone.lifetime.map_or(1.0, |lifetime| {
+                1.0 - lifetime.as_secs_f32() / damage_zone.duration.as_secs_f32()
+            });
+            *collider = damage_zone.hitbox.collider(progress);
+        }
 
-            let Ok(_) = damageable_query.get(colliding_entity) else {
+        let now = time.elapsed();
+        for colliding_entity in in_stable_order(colliding_entities, &transform_query) {
+            // Hitboxes can be children of the entity that has health.
+            let target = collider_parents
+                .get(colliding_entity)
+                .map_or(colliding_entity, |p| p.get());
+            if !damaged_entities.can_hit(target, now, damage_zone.tick_interval)
+                || !damageable_query.contains(target)
+            {
                 continue;
-            };
+            }
 
-            damaged_entities.0.insert(colliding_entity);
+            damaged_entities.insert(target, now);
             events.send(DamageEvent {
-                damage: damage_zone.damage,
-                target: colliding_entity,
-                source: damage_zone.emitter,
+                status_effect: inflicts_status_effect.map(|i| i.0),
+                knockback: damage_zone.knockback,
+                damage_over_time: damage_zone.damage_over_time,
+                origin: Some(global_transform.translation().xy()),
+                melee: damage_zone.melee,
+                attack: name.cloned(),
+                ..DamageEvent::new(
+                    damage_zone.damage,
+                    damage_zone.kind,
+                    target,
+                    damage_zone.emitter,
+                )
             });
         }
     }
 }
+
+/// The colliding entities, ordered by position rather than by entity.
+///
+/// Entities don't get the same ids when a run is replayed, so hitting them in that order
+/// would make crit rolls land on different targets.
+pub(super) fn in_stable_order(
+    colliding_entities: &CollidingEntities,
+    transform_query: &Query<&GlobalTransform>,
+) -> Vec<Entity> {
+    let position = |entity: &Entity| {
+        transform_query
+            .get(*entity)
+            .map_or(Vec2::ZERO, |transform| transform.translation().xy())
+    };
+    let mut entities: Vec<Entity> = colliding_entities.iter().copied().collect();
+    entities.sort_by(|a, b| {
+        let (a, b) = (position(a), position(b));
+        a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+    });
+    entities
+}
+
+fn send_damage_zone_events(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    mut entered_events: EventWriter<DamageZoneEntered>,
+    mut exited_events: EventWriter<DamageZoneExited>,
+    zone_query: Query<(), With<DamageZone>>,
+    collider_parents: Query<&ColliderParent>,
+    damageable_query: Query<(), With<Health>>,
+) {
+    // The zone and the entity, whichever way around the collision is.
+    let zone_and_entity = |a: Entity, b: Entity| {
+        let (zone, other) = if zone_query.contains(a) {
+            (a, b)
+        } else if zone_query.contains(b) {
+            (b, a)
+        } else {
+            return None;
+        };
+        let entity = collider_parents.get(other).map_or(other, |p| p.get());
+        damageable_query.contains(entity).then_some((zone, entity))
+    };
+    for &CollisionStarted(a, b) in started.read() {
+        if let Some((zone, entity)) = zone_and_entity(a, b) {
+            entered_events.send(DamageZoneEntered { zone, entity });
+        }
+    }
+    for &CollisionEnded(a, b) in ended.read() {
+        if let Some((zone, entity)) = zone_and_entity(a, b) {
+            exited_events.send(DamageZoneExited { zone, entity });
+        }
+    }
+}
@@ -2,11 +2,13 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 
 use super::{
-    health::{DamageEvent, Health},
+    death::Dying,
+    health::{DamageEvent, DamageKind, Health},
     spawn::{
         melee_enemy::MeleeEnemy,
         player::{Player, PlayerHitBox},
     },
+    status_effect::InflictsStatusEffect,
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -15,7 +17,15 @@ pub(super) fn plugin(app: &mut App) {
 
 fn handle_damaging_contacts(
     mut events: EventWriter<DamageEvent>,
-    query: Query<(Entity, &CollidingEntities), With<MeleeEnemy>>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &CollidingEntities,
+            Option<&InflictsStatusEffect>,
+        ),
+        (With<MeleeEnemy>, Without<Dying>),
+    >,
     player_query: Query<Entity, (With<Health>, With<Player>)>,
     player_hit_box_query: Query<Entity, With<PlayerHitBox>>,
 ) {
@@ -27,12 +37,14 @@ fn handle_damaging_contacts(
         return;
     };
 
-    for (enemy_entity, enemy_colliding_entities) in &query {
+    for (enemy_entity, enemy_transform, enemy_colliding_entities, inflicts_status_effect) in &query
+    {
         if enemy_colliding_entities.0.contains(&player_hitbox_entity) {
             events.send(DamageEvent {
-                damage: 1.0,
-                target: player_entity,
-                source: enemy_entity,
+                status_effect: inflicts_status_effect.map(|i| i.0),
+                origin: Some(enemy_transform.translation.xy()),
+                melee: true,
+                ..DamageEvent::new(1.0, DamageKind::Physical, player_entity, enemy_entity)
             });
         }
     }
@@ -6,16 +6,39 @@ use leafwing_input_manager::prelude::*;
 
 use crate::AppSet;
 
-use super::{input::PlayerAction, movement::Movement, spawn::player::Player};
+use super::{
+    ability::{Casting, CastingBundle},
+    attack::Attack,
+    cancel::{BusyState, CancelAction, CancelRules},
+    death::Dying,
+    input::PlayerAction,
+    knockback::Hitstun,
+    movement::Movement,
+    spawn::player::Player,
+    ui::percentage::{AsPercentage, Percentage},
+};
 
 const DASH_ROTATION_SPEED: f32 = 7.5;
+/// How long a press made while the dash can't be used is kept, to dash once it can.
+const DASH_BUFFER: Duration = Duration::from_millis(200);
+/// How long after landing an attack still counts as a dash attack.
+const DASH_ATTACK_WINDOW: Duration = Duration::from_millis(150);
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<DashController>();
     app.add_systems(Update, record_dash_controller.in_set(AppSet::RecordInput));
 
     app.register_type::<Dash>();
-    app.add_systems(Update, apply_dash.in_set(AppSet::Update));
+    app.add_systems(Update, recharge_dashes.in_set(AppSet::TickTimers));
+    app.register_type::<Dodging>();
+    app.register_type::<PassingThrough>();
+    app.add_event::<PerfectDodgeEvent>();
+    app.add_systems(
+        Update,
+        (apply_dash, update_dodging, update_pass_through)
+            .chain()
+            .in_set(AppSet::Update),
+    );
 }
 
 #[derive(Component, Reflect, Default)]
@@ -63,36 +86,140 @@ pub enum DashState {
     Ready,
     Dashing(Duration),
     Landing(Duration),
-    OnCooldown(Duration),
 }
 
-#[derive(Component, Reflect, Default)]
+/// The part of a dash during which the dasher can't be hit, measured from its start.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct DashIFrames {
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// Dashes are spent from stored charges, which recharge one at a time.
+/// Every field can be changed at runtime, e.g. by upgrades.
+#[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct Dash {
     pub state: DashState,
-    pub speed: f32,
+    /// How far a dash goes.
+    pub distance: f32,
     pub duration: Duration,
     pub landing_duration: Duration,
-    pub cooldown: Duration,
+    pub max_charges: u32,
+    pub charges: u32,
+    /// Time to recharge a single charge.
+    pub recharge_time: Duration,
+    /// Progress of the charge currently recharging.
+    pub recharge: Duration,
+    pub iframes: Option<DashIFrames>,
+    /// Layers the dasher's hitboxes stop colliding with while dashing, e.g. to pass through enemies.
+    /// Not during the i-frames, so that hits there are still dodged.
+    pub pass_through: LayerMask,
+    /// The speed of the current dash, which can differ for dashes from abilities.
+    dashing_speed: f32,
+    dashing_duration: Duration,
+    /// Time left to use a press made while the dash couldn't be used.
+    buffered: Duration,
+    /// Time left after landing during which attacks are dash attacks.
+    dash_attack_window: Duration,
+}
+
+impl AsPercentage for Dash {
+    /// Stored charges count as full segments, the recharging one as a partial segment.
+    fn percentage(&self) -> Percentage {
+        if self.max_charges == 0 {
+            return Percentage::new(0.0);
+        }
+        let recharging = if self.recharge_time.is_zero() {
+            0.0
+        } else {
+            self.recharge.as_secs_f32() / self.recharge_time.as_secs_f32()
+        };
+        Percentage::new((self.charges as f32 + recharging) / self.max_charges as f32)
+    }
+
+    fn segments(&self) -> u32 {
+        self.max_charges
+    }
 }
 
 impl Dash {
+    /// A dash with a single charge.
     pub fn new(
-        speed: f32,
+        distance: f32,
         duration: Duration,
         landing_duration: Duration,
-        cooldown: Duration,
+        recharge_time: Duration,
     ) -> Dash {
         Dash {
             state: DashState::Ready,
-            speed,
+            distance,
             duration,
             landing_duration,
-            cooldown,
+            max_charges: 1,
+            charges: 1,
+            recharge_time,
+            recharge: Duration::ZERO,
+            iframes: None,
+            pass_through: LayerMask::NONE,
+            dashing_speed: 0.0,
+            dashing_duration: duration,
+            buffered: Duration::ZERO,
+            dash_attack_window: Duration::ZERO,
+        }
+    }
+
+    pub fn with_charges(mut self, charges: u32) -> Self {
+        self.max_charges = charges;
+        self.charges = charges;
+        self
+    }
+
+    pub fn with_iframes(mut self, start: Duration, duration: Duration) -> Self {
+        self.iframes = Some(DashIFrames { start, duration });
+        self
+    }
+
+    pub fn with_pass_through(mut self, layers: impl Into<LayerMask>) -> Self {
+        self.pass_through = layers.into();
+        self
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        matches!(self.state, DashState::Dashing(_))
+    }
+
+    pub fn busy_state(&self) -> Option<BusyState> {
+        match self.state {
+            DashState::Dashing(_) => Some(BusyState::Dashing),
+            DashState::Landing(_) => Some(BusyState::DashLanding),
+            DashState::Ready => None,
         }
     }
 
+    /// Whether an attack now would be a dash attack: during the dash or right after it.
+    pub fn in_dash_attack_window(&self) -> bool {
+        self.busy_state().is_some() || !self.dash_attack_window.is_zero()
+    }
+
+    /// End the dash right away, e.g. when it turns into a dash attack.
+    pub fn stop(&mut self) {
+        self.state = DashState::Ready;
+        self.dash_attack_window = Duration::ZERO;
+    }
+
+    /// Whether the current dash is in its i-frames.
+    pub fn is_invulnerable(&self) -> bool {
+        let (DashState::Dashing(remaining), Some(iframes)) = (&self.state, self.iframes) else {
+            return false;
+        };
+        let elapsed = self.dashing_duration.saturating_sub(*remaining);
+        elapsed >= iframes.start && elapsed < iframes.start + iframes.duration
+    }
+
     fn apply_delta_time(&mut self, delta_time: Duration) {
+        self.buffered = self.buffered.saturating_sub(delta_time);
+        self.dash_attack_window = self.dash_attack_window.saturating_sub(delta_time);
         match &mut self.state {
             DashState::Dashing(duration) => {
                 *duration = duration.saturating_sub(delta_time);
@@ -101,49 +228,117 @@ impl Dash {
                 }
             }
             DashState::Landing(duration) => {
-                *duration = duration.saturating_sub(delta_time);
-                if *duration <= Duration::ZERO {
-                    self.state = DashState::OnCooldown(self.cooldown);
-                }
-            }
-            DashState::OnCooldown(duration) => {
                 *duration = duration.saturating_sub(delta_time);
                 if *duration <= Duration::ZERO {
                     self.state = DashState::Ready;
+                    self.dash_attack_window = DASH_ATTACK_WINDOW;
                 }
             }
             DashState::Ready => (),
         }
     }
 
+    /// Recharge one charge at a time, and drop charges above the maximum.
+    fn recharge(&mut self, delta_time: Duration) {
+        self.charges = self.charges.min(self.max_charges);
+        if self.charges == self.max_charges {
+            self.recharge = Duration::ZERO;
+            return;
+        }
+        self.recharge += delta_time;
+        if self.recharge >= self.recharge_time {
+            self.recharge = Duration::ZERO;
+            self.charges += 1;
+        }
+    }
+
+    /// Whether a dash can start now, ignoring other actions.
+    fn can_dash(&self) -> bool {
+        matches!(self.state, DashState::Ready) && self.charges > 0
+    }
+
+    /// Spend a charge to dash, using up the buffered press.
     fn request_dash(&mut self) {
-        if let DashState::Ready = self.state {
-            self.state = DashState::Dashing(self.duration);
+        if !self.can_dash() {
+            return;
         }
+        self.charges -= 1;
+        self.buffered = Duration::ZERO;
+        let speed = self.distance / self.duration.as_secs_f32().max(f32::EPSILON);
+        self.start(speed, self.duration);
+    }
+
+    /// Dash right away, whatever the current state. Doesn't use a charge.
+    pub fn start(&mut self, speed: f32, duration: Duration) {
+        self.dashing_speed = speed;
+        self.dashing_duration = duration;
+        self.state = DashState::Dashing(duration);
     }
 }
 
-fn apply_dash(
+fn recharge_dashes(time: Res<Time>, mut query: Query<&mut Dash, Without<Dying>>) {
+    for mut dash in &mut query {
+        dash.recharge(time.delta());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn apply_dash(
+    mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(
-        &DashController,
-        &mut Dash,
-        &mut Movement,
-        &mut LinearVelocity,
-    )>,
+    mut query: Query<
+        (
+            Entity,
+            &DashController,
+            &mut Dash,
+            &mut Movement,
+            &mut LinearVelocity,
+            Option<&mut Attack>,
+            Option<&Casting>,
+            Option<&CancelRules>,
+        ),
+        (Without<Dying>, Without<Hitstun>),
+    >,
 ) {
-    for (controller, mut dash, mut movement, mut linear_velocity) in &mut query {
+    for (
+        entity,
+        controller,
+        mut dash,
+        mut movement,
+        mut linear_velocity,
+        attack,
+        casting,
+        cancel_rules,
+    ) in &mut query
+    {
         dash.apply_delta_time(time.delta());
 
         if controller.intent {
-            dash.request_dash()
+            dash.buffered = DASH_BUFFER;
+        }
+        if !dash.buffered.is_zero() && dash.can_dash() {
+            let attack_state = attack.as_ref().and_then(|attack| attack.busy_state());
+            let casting_state = casting.map(|_| BusyState::Casting);
+            let allowed = [attack_state, casting_state]
+                .into_iter()
+                .flatten()
+                .all(|state| cancel_rules.is_some_and(|r| r.allows(state, CancelAction::Dash)));
+            if allowed {
+                if let Some(mut attack) = attack {
+                    attack.cancel();
+                }
+                if casting.is_some() {
+                    commands.entity(entity).remove::<CastingBundle>();
+                }
+                dash.request_dash();
+            }
         }
 
         match dash.state {
             DashState::Dashing(_) => {
                 movement.toggle_control(false);
                 if linear_velocity.length_squared() <= 0.05 {
-                    linear_velocity.0 = dash.speed * controller.last_direction;
+                    linear_velocity.0 = dash.dashing_speed * controller.last_direction;
                 } else {
                     let intent_angle = linear_velocity.angle_between(controller.last_direction);
                     let max_angle = DASH_ROTATION_SPEED * time.delta_seconds();
@@ -151,15 +346,216 @@ fn apply_dash(
                     let new_direction = Vec2::from_angle(angle)
                         .rotate(linear_velocity.xy())
                         .normalize_or_zero();
-                    linear_velocity.0 = dash.speed * new_direction;
+                    linear_velocity.0 = dash.dashing_speed * new_direction;
                 }
             }
             DashState::Landing(_) => {
                 movement.toggle_control(false);
                 linear_velocity.0 = Vec2::ZERO;
             }
-            DashState::OnCooldown(_) => movement.toggle_control(true),
             DashState::Ready => movement.toggle_control(true),
         }
     }
 }
+
+/// A dasher in its i-frames. Hits are dodged instead of landing.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Dodging {
+    /// Sources whose hits were already dodged, so each counts once per dash.
+    pub dodged: Vec<Entity>,
+}
+
+/// Sent when a hit is dodged during a dash's i-frames.
+#[derive(Event, Debug)]
+pub struct PerfectDodgeEvent {
+    pub entity: Entity,
+    pub source: Entity,
+}
+
+fn update_dodging(mut commands: Commands, query: Query<(Entity, &Dash, Has<Dodging>)>) {
+    for (entity, dash, dodging) in &query {
+        if dash.is_invulnerable() && !dodging {
+            commands.entity(entity).insert(Dodging::default());
+        } else if !dash.is_invulnerable() && dodging {
+            commands.entity(entity).remove::<Dodging>();
+        }
+    }
+}
+
+/// Filters removed from a hitbox by [`Dash::pass_through`], to restore after the dash.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct PassingThrough(LayerMask);
+
+#[allow(clippy::type_complexity)]
+fn update_pass_through(
+    mut commands: Commands,
+    query: Query<(Entity, &Dash, Option<&Children>)>,
+    mut hitbox_query: Query<(&mut CollisionLayers, Option<&PassingThrough>), With<Sensor>>,
+) {
+    for (entity, dash, children) in &query {
+        let passing_through =
+            dash.is_dashing() && !dash.is_invulnerable() && dash.pass_through != LayerMask::NONE;
+        // The dasher's hitboxes are sensors on itself or its children.
+        let hitboxes = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        for hitbox in hitboxes {
+            let Ok((mut layers, removed)) = hitbox_query.get_mut(hitbox) else {
+                continue;
+            };
+            match (passing_through, removed) {
+                (true, None) => {
+                    let removed = layers.filters & dash.pass_through;
+                    layers.filters &= !dash.pass_through;
+                    commands.entity(hitbox).insert(PassingThrough(removed));
+                }
+                (false, Some(removed)) => {
+                    layers.filters |= removed.0;
+                    commands.entity(hitbox).remove::<PassingThrough>();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        block::BlockEvent,
+        death::DeathEvent,
+        health::{process_damage_events, DamageDealtEvent, DamageEvent, DamageKind, Health},
+        knockback::KnockbackEvent,
+        rng::GameRng,
+        status_effect::ApplyStatusEffectEvent,
+        GameLayer,
+    };
+
+    fn dash_with_charges(charges: u32) -> Dash {
+        Dash::new(
+            120.0,
+            Duration::from_millis(200),
+            Duration::from_millis(100),
+            Duration::from_secs(2),
+        )
+        .with_charges(charges)
+    }
+
+    #[test]
+    fn recharge_restores_one_charge_at_a_time() {
+        let mut dash = dash_with_charges(3);
+        dash.charges = 0;
+        dash.recharge(Duration::from_millis(1500));
+        assert_eq!(dash.charges, 0);
+        // Time past the recharge time doesn't carry over to the next charge.
+        dash.recharge(Duration::from_millis(1500));
+        assert_eq!(dash.charges, 1);
+        assert_eq!(dash.recharge, Duration::ZERO);
+        dash.recharge(Duration::from_secs(5));
+        assert_eq!(dash.charges, 2);
+    }
+
+    #[test]
+    fn recharge_stops_at_max_charges() {
+        let mut dash = dash_with_charges(2);
+        dash.charges = 1;
+        dash.recharge(Duration::from_secs(2));
+        assert_eq!(dash.charges, 2);
+        dash.recharge(Duration::from_secs(1));
+        assert_eq!((dash.charges, dash.recharge), (2, Duration::ZERO));
+    }
+
+    #[test]
+    fn recharge_drops_charges_above_max() {
+        let mut dash = dash_with_charges(2);
+        dash.charges = 4;
+        dash.recharge(Duration::ZERO);
+        assert_eq!(dash.charges, 2);
+    }
+
+    #[test]
+    fn enemy_contacts_are_skipped_while_passing_through() {
+        let mut app = App::new();
+        app.add_systems(Update, update_pass_through);
+
+        let mut dash = dash_with_charges(1)
+            .with_iframes(Duration::ZERO, Duration::from_millis(100))
+            .with_pass_through(GameLayer::Enemies);
+        dash.start(600.0, Duration::from_millis(200));
+        let player = app.world_mut().spawn(dash).id();
+        let hitbox = app
+            .world_mut()
+            .spawn((
+                CollisionLayers::new(GameLayer::PlayerHitbox, GameLayer::Enemies),
+                Sensor,
+            ))
+            .id();
+        app.world_mut().entity_mut(player).add_child(hitbox);
+        let enemy_layers = CollisionLayers::new(
+            GameLayer::Enemies,
+            [GameLayer::Enemies, GameLayer::PlayerHitbox],
+        );
+        let touches_enemies = |app: &mut App, delta_time: Duration| {
+            let mut dash = app.world_mut().get_mut::<Dash>(player).unwrap();
+            dash.apply_delta_time(delta_time);
+            app.update();
+            let layers = app.world().get::<CollisionLayers>(hitbox).unwrap();
+            layers.interacts_with(enemy_layers)
+        };
+
+        // Hits during the i-frames still reach the hitbox, to be dodged.
+        assert!(touches_enemies(&mut app, Duration::ZERO));
+        assert!(!touches_enemies(&mut app, Duration::from_millis(150)));
+        // Landing.
+        assert!(touches_enemies(&mut app, Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn contact_hit_during_iframes_is_a_perfect_dodge() {
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .add_event::<DamageDealtEvent>()
+            .add_event::<DeathEvent>()
+            .add_event::<ApplyStatusEffectEvent>()
+            .add_event::<KnockbackEvent>()
+            .add_event::<BlockEvent>()
+            .add_event::<PerfectDodgeEvent>()
+            .insert_resource(GameRng::new(0))
+            .add_systems(
+                Update,
+                (update_dodging, update_pass_through, process_damage_events).chain(),
+            );
+
+        let mut dash = dash_with_charges(1)
+            .with_iframes(Duration::ZERO, Duration::from_millis(150))
+            .with_pass_through(GameLayer::Enemies);
+        dash.start(600.0, Duration::from_millis(200));
+        let player = app.world_mut().spawn((Health::new(100.0), dash)).id();
+        let hitbox = app
+            .world_mut()
+            .spawn((
+                CollisionLayers::new(GameLayer::PlayerHitbox, GameLayer::Enemies),
+                Sensor,
+            ))
+            .id();
+        app.world_mut().entity_mut(player).add_child(hitbox);
+        let enemy = app.world_mut().spawn_empty().id();
+
+        // Let the dodge start before the enemy's contact lands.
+        app.update();
+        app.world_mut().send_event(DamageEvent {
+            origin: Some(Vec2::X),
+            melee: true,
+            ..DamageEvent::new(1.0, DamageKind::Physical, player, enemy)
+        });
+        app.update();
+
+        let dodges = app.world().resource::<Events<PerfectDodgeEvent>>();
+        let mut reader = dodges.get_reader();
+        let dodge = reader.read(dodges).next().unwrap();
+        assert_eq!((dodge.entity, dodge.source), (player, enemy));
+        let health = app.world().get::<Health>(player).unwrap();
+        assert_eq!(health.hit_points, 100.0);
+    }
+}
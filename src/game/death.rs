@@ -0,0 +1,170 @@
+//! Turn entities that ran out of hit points into dying entities, then corpses or nothing.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    ability::AbilityController,
+    attack::AttackController,
+    block::BlockController,
+    dash::DashController,
+    health::{Health, Shield},
+    movement::MovementController,
+    ui::status_bar::definition::StatusBarDefinition,
+};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<DeathEvent>();
+    app.add_event::<DoneDyingEvent>();
+    app.register_type::<DeathBehaviour>();
+    app.register_type::<Dying>();
+    app.register_type::<Corpse>();
+    app.add_systems(
+        Update,
+        (
+            tick_dying.in_set(AppSet::TickTimers),
+            (start_dying, fade_dying_sprites).in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// Sent once when an entity runs out of hit points.
+/// This is the hook for loot and score.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    /// The source of the killing blow.
+    pub killer: Entity,
+}
+
+/// Sent once when an entity is done dying, right before it is despawned or left as a [`Corpse`].
+/// This is the hook for the game-over flow.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DoneDyingEvent {
+    pub entity: Entity,
+}
+
+/// What happens to an entity once it runs out of hit points.
+/// Entities without this component are despawned right away.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct DeathBehaviour {
+    /// How long the entity stays [`Dying`], to leave room for a death animation and sfx.
+    pub dying_duration: Duration,
+    /// Whether the entity is despawned once it is done dying, or left behind as a [`Corpse`].
+    pub despawn: bool,
+}
+
+impl DeathBehaviour {
+    pub fn despawn_after(dying_duration: Duration) -> Self {
+        DeathBehaviour {
+            dying_duration,
+            despawn: true,
+        }
+    }
+
+    pub fn corpse_after(dying_duration: Duration) -> Self {
+        DeathBehaviour {
+            dying_duration,
+            despawn: false,
+        }
+    }
+}
+
+/// An entity that died and is playing out its death.
+/// Gameplay systems ignore dying entities.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Dying {
+    pub duration: Duration,
+    pub remaining: Duration,
+}
+
+impl Dying {
+    fn new(duration: Duration) -> Self {
+        Dying {
+            duration,
+            remaining: duration,
+        }
+    }
+
+    /// Progress through the dying phase, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        1.0 - self.remaining.as_secs_f32() / self.duration.as_secs_f32()
+    }
+}
+
+/// What is left of an entity once it is done dying, if its [`DeathBehaviour`] keeps it around.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Corpse;
+
+fn start_dying(
+    mut commands: Commands,
+    mut events: EventReader<DeathEvent>,
+    mut query: Query<(Option<&DeathBehaviour>, Option<&mut LinearVelocity>), Without<Dying>>,
+) {
+    for &DeathEvent { entity, .. } in events.read() {
+        let Ok((behaviour, linear_velocity)) = query.get_mut(entity) else {
+            continue;
+        };
+        if let Some(mut linear_velocity) = linear_velocity {
+            linear_velocity.0 = Vec2::ZERO;
+        }
+        let dying_duration = behaviour.map_or(Duration::ZERO, |b| b.dying_duration);
+        commands.entity(entity).insert(Dying::new(dying_duration));
+    }
+}
+
+fn tick_dying(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut events: EventWriter<DoneDyingEvent>,
+    mut query: Query<(Entity, &mut Dying, Option<&DeathBehaviour>)>,
+) {
+    for (entity, mut dying, behaviour) in &mut query {
+        dying.remaining = dying.remaining.saturating_sub(time.delta());
+        if dying.remaining > Duration::ZERO {
+            continue;
+        }
+
+        events.send(DoneDyingEvent { entity });
+        if behaviour.is_none_or(|b| b.despawn) {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            commands
+                .entity(entity)
+                .remove::<(
+                    Dying,
+                    Health,
+                    StatusBarDefinition<Health>,
+                    Shield,
+                    StatusBarDefinition<Shield>,
+                    RigidBody,
+                    Collider,
+                )>()
+                // Corpses can't act anymore.
+                .remove::<(
+                    MovementController,
+                    AttackController,
+                    DashController,
+                    BlockController,
+                    AbilityController,
+                )>()
+                .insert(Corpse);
+        }
+    }
+}
+
+/// Fade dying sprites out until a proper death animation exists.
+fn fade_dying_sprites(mut query: Query<(&Dying, &mut Sprite)>) {
+    for (dying, mut sprite) in &mut query {
+        sprite.color.set_alpha(1.0 - 0.7 * dying.progress());
+    }
+}
@@ -1,10 +1,38 @@
+use std::time::Duration;
+
+use super::{
+    ability::Buffs,
+    block::{Block, BlockEvent, Guard},
+    damage_modifiers::{modify_damage, DamageDealtModifiers, DamageTakenModifiers},
+    dash::{Dodging, PerfectDodgeEvent},
+    death::DeathEvent,
+    knockback::{Knockback, KnockbackEvent},
+    rng::GameRng,
+    status_effect::{ApplyStatusEffectEvent, StatusEffect},
+};
 use crate::game::ui::percentage::{AsPercentage, Percentage};
-use bevy::prelude::*;
+use crate::AppSet;
+use bevy::{prelude::*, utils::HashMap};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Update, process_damage_events)
-        .add_systems(Update, handle_death)
-        .add_event::<DamageEvent>();
+    app.register_type::<Health>();
+    app.register_type::<Resistances>();
+    app.register_type::<Invulnerable>();
+    app.register_type::<HitCooldowns>();
+    app.register_type::<Shield>();
+    app.register_type::<Regeneration>();
+    app.add_systems(Update, tick_hit_protection.in_set(AppSet::TickTimers))
+        .add_systems(
+            Update,
+            (
+                (process_damage_events, process_heal_events).chain(),
+                apply_regeneration.in_set(AppSet::Update),
+            ),
+        )
+        .add_event::<DamageEvent>()
+        .add_event::<DamageDealtEvent>()
+        .add_event::<HealEvent>()
+        .add_event::<HealedEvent>();
 }
 
 #[derive(Component, Reflect)]
@@ -27,31 +55,491 @@ impl Health {
             max,
         }
     }
+
+    pub fn is_dead(&self) -> bool {
+        self.hit_points <= 0.0
+    }
+
+    /// Heal up to [`Health::max`] and return the amount that did not fit.
+    fn heal(&mut self, amount: f32) -> f32 {
+        let healed = f32::min(amount, self.max - self.hit_points).max(0.0);
+        self.hit_points += healed;
+        amount - healed
+    }
+}
+
+/// A pool of extra hit points that absorbs damage before [`Health`].
+/// It is filled by healing that goes past [`Health::max`].
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Shield {
+    pub points: f32,
+    pub max: f32,
+}
+
+impl AsPercentage for Shield {
+    fn percentage(&self) -> Percentage {
+        Percentage::new(self.points / self.max)
+    }
+}
+
+impl Shield {
+    pub fn new(max: f32) -> Self {
+        Shield { points: 0.0, max }
+    }
+
+    /// Absorb as much of `damage` as possible and return the absorbed amount.
+    fn absorb(&mut self, damage: f32) -> f32 {
+        let absorbed = f32::min(damage, self.points);
+        self.points -= absorbed;
+        absorbed
+    }
+
+    /// Fill the shield up to [`Shield::max`] and return the amount that fit.
+    fn fill(&mut self, amount: f32) -> f32 {
+        let filled = f32::min(amount, self.max - self.points).max(0.0);
+        self.points += filled;
+        filled
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub enum RegenerationRate {
+    /// Hit points per second.
+    Flat(f32),
+    /// Fraction of [`Health::max`] per second.
+    Percentage(f32),
+}
+
+/// Restores hit points over time, unless the entity was damaged recently.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Regeneration {
+    pub rate: RegenerationRate,
+    /// How long regeneration stops after a hit lands.
+    pub pause_after_damage: Duration,
+    pub paused: Duration,
+}
+
+impl Regeneration {
+    pub fn new(rate: RegenerationRate, pause_after_damage: Duration) -> Self {
+        Regeneration {
+            rate,
+            pause_after_damage,
+            paused: Duration::ZERO,
+        }
+    }
+
+    fn interrupt(&mut self) {
+        self.paused = self.pause_after_damage;
+    }
+
+    fn hit_points_per_second(&self, health: &Health) -> f32 {
+        match self.rate {
+            RegenerationRate::Flat(hit_points) => hit_points,
+            RegenerationRate::Percentage(fraction) => fraction * health.max,
+        }
+    }
+}
+
+/// The kind of a hit, used to look up the target's [`Resistances`].
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DamageKind {
+    #[default]
+    Physical,
+    Fire,
+    Poison,
+    Spirit,
+}
+
+/// Per-kind damage resistances.
+/// A resistance of `0.25` removes a quarter of the incoming damage,
+/// `1.0` makes the entity immune and a negative value makes it vulnerable.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub poison: f32,
+    pub spirit: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, kind: DamageKind) -> f32 {
+        match kind {
+            DamageKind::Physical => self.physical,
+            DamageKind::Fire => self.fire,
+            DamageKind::Poison => self.poison,
+            DamageKind::Spirit => self.spirit,
+        }
+    }
+
+    /// Return the damage that gets through these resistances.
+    pub fn mitigate(&self, damage: f32, kind: DamageKind) -> f32 {
+        f32::max(damage * (1.0 - self.get(kind)), 0.0)
+    }
+}
+
+/// Makes an entity ignore damage for a while after each hit that lands.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Invulnerable {
+    /// How long the entity stays invulnerable after taking a hit.
+    pub on_hit: Duration,
+    pub remaining: Duration,
+}
+
+impl Invulnerable {
+    pub fn new(on_hit: Duration) -> Self {
+        Invulnerable {
+            on_hit,
+            remaining: Duration::ZERO,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > Duration::ZERO
+    }
+
+    /// Make the entity invulnerable for at least `duration`.
+    pub fn grant(&mut self, duration: Duration) {
+        self.remaining = self.remaining.max(duration);
+    }
+
+    fn apply_delta_time(&mut self, delta_time: Duration) {
+        self.remaining = self.remaining.saturating_sub(delta_time);
+    }
+}
+
+/// Prevents the same source from hitting an entity again before `cooldown` elapsed.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct HitCooldowns {
+    pub cooldown: Duration,
+    pub remaining: HashMap<Entity, Duration>,
+}
+
+impl HitCooldowns {
+    pub fn new(cooldown: Duration) -> Self {
+        HitCooldowns {
+            cooldown,
+            remaining: HashMap::default(),
+        }
+    }
+
+    pub fn is_cooling_down(&self, source: Entity) -> bool {
+        self.remaining.contains_key(&source)
+    }
+
+    fn start(&mut self, source: Entity) {
+        self.remaining.insert(source, self.cooldown);
+    }
+
+    fn apply_delta_time(&mut self, delta_time: Duration) {
+        self.remaining.retain(|_, remaining| {
+            *remaining = remaining.saturating_sub(delta_time);
+            *remaining > Duration::ZERO
+        });
+    }
+}
+
+fn tick_hit_protection(
+    time: Res<Time>,
+    mut invulnerable_query: Query<&mut Invulnerable>,
+    mut cooldowns_query: Query<&mut HitCooldowns>,
+) {
+    for mut invulnerable in &mut invulnerable_query {
+        invulnerable.apply_delta_time(time.delta());
+    }
+    for mut cooldowns in &mut cooldowns_query {
+        cooldowns.apply_delta_time(time.delta());
+    }
 }
 
 #[derive(Event)]
 pub struct DamageEvent {
     pub damage: f32,
+    pub kind: DamageKind,
+    pub target: Entity,
+    pub source: Entity,
+    /// An effect to apply to the target if the hit lands.
+    pub status_effect: Option<StatusEffect>,
+    /// Pushes the target away from the source if the hit lands.
+    pub knockback: Option<Knockback>,
+    /// Damage over time ticks are not blocked by, and don't start, i-frames or re-hit cooldowns.
+    pub damage_over_time: bool,
+    /// Where the hit comes from, e.g. the projectile rather than whoever fired it.
+    /// Hits without one can't be guarded against.
+    pub origin: Option<Vec2>,
+    /// Whether the source struck in person. Only melee sources are staggered by a parry.
+    pub melee: bool,
+    /// What the source hit with, e.g. `SwordSlash` or `Burn`, if not with itself.
+    pub attack: Option<Name>,
+}
+
+impl DamageEvent {
+    pub fn new(damage: f32, kind: DamageKind, target: Entity, source: Entity) -> Self {
+        DamageEvent {
+            damage,
+            kind,
+            target,
+            source,
+            status_effect: None,
+            knockback: None,
+            damage_over_time: false,
+            origin: None,
+            melee: false,
+            attack: None,
+        }
+    }
+}
+
+/// Sent once a [`DamageEvent`] has been resolved, with the amount that actually landed.
+#[derive(Event, Debug, Clone)]
+pub struct DamageDealtEvent {
+    pub amount: f32,
+    /// The part of `amount` that was absorbed by a [`Shield`].
+    pub absorbed: f32,
+    pub kind: DamageKind,
     pub target: Entity,
-    #[allow(dead_code)]
     pub source: Entity,
+    pub critical: bool,
+    /// Whether this hit killed the target.
+    pub killing_blow: bool,
+    pub damage_over_time: bool,
+    pub attack: Option<Name>,
 }
 
 pub fn process_damage_events(
     mut events: EventReader<DamageEvent>,
-    mut health_query: Query<&mut Health>,
+    mut dealt_events: EventWriter<DamageDealtEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut status_effect_events: EventWriter<ApplyStatusEffectEvent>,
+    mut knockback_events: EventWriter<KnockbackEvent>,
+    mut block_events: EventWriter<BlockEvent>,
+    mut dodge_events: EventWriter<PerfectDodgeEvent>,
+    mut health_query: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&DamageTakenModifiers>,
+        Option<&mut Invulnerable>,
+        Option<&mut HitCooldowns>,
+        Option<&mut Shield>,
+        Option<&mut Regeneration>,
+    )>,
+    attacker_query: Query<(Option<&DamageDealtModifiers>, Option<&Buffs>)>,
+    block_query: Query<&Block>,
+    mut dodging_query: Query<&mut Dodging>,
+    transform_query: Query<&Transform>,
+    mut rng: ResMut<GameRng>,
+) {
+    for &DamageEvent {
+        damage,
+        kind,
+        target,
+        source,
+        status_effect,
+        knockback,
+        damage_over_time,
+        origin,
+        melee,
+        ref attack,
+    } in events.read()
+    {
+        let Ok((
+            mut health,
+            resistances,
+            taken_modifiers,
+            invulnerable,
+            cooldowns,
+            shield,
+            regeneration,
+        )) = health_query.get_mut(target)
+        else {
+            continue;
+        };
+
+        if health.is_dead() {
+            continue;
+        }
+        let mut taken_modifiers = taken_modifiers.copied();
+        if !damage_over_time {
+            if let Ok(mut dodging) = dodging_query.get_mut(target) {
+                if !dodging.dodged.contains(&source) {
+                    dodging.dodged.push(source);
+                    dodge_events.send(PerfectDodgeEvent {
+                        entity: target,
+                        source,
+                    });
+                }
+                continue;
+            }
+            if invulnerable.as_ref().is_some_and(|i| i.is_active())
+                || cooldowns
+                    .as_ref()
+                    .is_some_and(|c| c.is_cooling_down(source))
+            {
+                continue;
+            }
+            let guard = block_query.get(target).ok().and_then(|block| {
+                let direction = origin? - transform_query.get(target).ok()?.translation.xy();
+                Some((block, block.guard(direction)?))
+            });
+            if let Some((block, guard)) = guard {
+                block_events.send(BlockEvent {
+                    target,
+                    source,
+                    guard,
+                });
+                match guard {
+                    Guard::Parried => {
+                        // The attacker can't try again right away.
+                        if let Some(mut cooldowns) = cooldowns {
+                            cooldowns.start(source);
+                        }
+                        if melee {
+                            knockback_events.send(KnockbackEvent {
+                                knockback: block.stagger,
+                                target: source,
+                                source: target,
+                            });
+                        }
+                        continue;
+                    }
+                    // Blocking reduces the hit once the attacker's modifiers are in.
+                    Guard::Blocked => {
+                        let taken = taken_modifiers.get_or_insert_with(default);
+                        taken.multiplier *= block.damage_multiplier;
+                    }
+                }
+            }
+            if let Some(mut invulnerable) = invulnerable {
+                let on_hit = invulnerable.on_hit;
+                invulnerable.grant(on_hit);
+            }
+            if let Some(mut cooldowns) = cooldowns {
+                cooldowns.start(source);
+            }
+        }
+        if let Some(mut regeneration) = regeneration {
+            regeneration.interrupt();
+        }
+
+        // Buffs add to the attacker's percent bonus.
+        let dealt_modifiers = match attacker_query.get(source) {
+            Ok((modifiers, Some(buffs))) => {
+                let mut modifiers = modifiers.copied().unwrap_or_default();
+                modifiers.percent += buffs.damage_percent();
+                Some(modifiers)
+            }
+            Ok((modifiers, None)) => modifiers.copied(),
+            Err(_) => None,
+        };
+        let modified = modify_damage(
+            damage,
+            kind,
+            damage_over_time,
+            dealt_modifiers.as_ref(),
+            resistances,
+            taken_modifiers.as_ref(),
+            &mut *rng,
+        );
+        let amount = modified.amount;
+        let absorbed = shield.map_or(0.0, |mut shield| shield.absorb(amount));
+        health.hit_points -= amount - absorbed;
+        dealt_events.send(DamageDealtEvent {
+            amount,
+            absorbed,
+            kind,
+            target,
+            source,
+            critical: modified.critical,
+            killing_blow: health.is_dead(),
+            damage_over_time,
+            attack: attack.clone(),
+        });
+
+        if let Some(knockback) = knockback {
+            knockback_events.send(KnockbackEvent {
+                knockback,
+                target,
+                source,
+            });
+        }
+        if let Some(effect) = status_effect {
+            status_effect_events.send(ApplyStatusEffectEvent {
+                effect,
+                target,
+                source,
+            });
+        }
+
+        if health.is_dead() {
+            death_events.send(DeathEvent {
+                entity: target,
+                killer: source,
+            });
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct HealEvent {
+    pub amount: f32,
+    pub target: Entity,
+    pub source: Entity,
+}
+
+/// Sent once a [`HealEvent`] has been resolved, with the amount that was actually restored.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HealedEvent {
+    pub amount: f32,
+    /// The part of `amount` that overhealed into a [`Shield`].
+    pub overheal: f32,
+    pub target: Entity,
+    pub source: Entity,
+}
+
+pub fn process_heal_events(
+    mut events: EventReader<HealEvent>,
+    mut healed_events: EventWriter<HealedEvent>,
+    mut health_query: Query<(&mut Health, Option<&mut Shield>)>,
 ) {
-    for &DamageEvent { damage, target, .. } in events.read() {
-        if let Ok(mut health) = health_query.get_mut(target) {
-            health.hit_points -= damage;
+    for &HealEvent {
+        amount,
+        target,
+        source,
+    } in events.read()
+    {
+        let Ok((mut health, shield)) = health_query.get_mut(target) else {
+            continue;
+        };
+        if health.is_dead() {
+            continue;
         }
+
+        let excess = health.heal(amount);
+        let overheal = shield.map_or(0.0, |mut shield| shield.fill(excess));
+        healed_events.send(HealedEvent {
+            amount: amount - excess + overheal,
+            overheal,
+            target,
+            source,
+        });
     }
 }
 
-fn handle_death(mut commands: Commands, query: Query<(Entity, &Health)>) {
-    for (entity, health) in query.iter() {
-        if health.hit_points <= 0.0 {
-            commands.entity(entity).despawn_recursive();
+fn apply_regeneration(time: Res<Time>, mut query: Query<(&mut Health, &mut Regeneration)>) {
+    for (mut health, mut regeneration) in &mut query {
+        if !regeneration.paused.is_zero() {
+            regeneration.paused = regeneration.paused.saturating_sub(time.delta());
+            continue;
+        }
+        if health.is_dead() {
+            continue;
         }
+
+        let amount = regeneration.hit_points_per_second(&health) * time.delta_seconds();
+        health.heal(amount);
     }
 }
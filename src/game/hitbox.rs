@@ -0,0 +1,187 @@
+//! Shapes for the hitboxes of damage zones, described as data.
+//!
+//! Shapes face +X, so they point where their damage zone is rotated to.
+
+use std::f32::consts::TAU;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+/// Angle covered by each segment when approximating curved shapes with polygons.
+const SEGMENT_ANGLE: f32 = TAU / 32.0;
+
+#[derive(Reflect, Clone, Debug)]
+// `Compound` makes the type recursive, which the default field bounds can't express.
+#[reflect(no_field_bounds)]
+pub enum HitboxShape {
+    Circle {
+        radius: f32,
+    },
+    /// A cone centered on +X, covering `angle` radians.
+    Arc {
+        radius: f32,
+        angle: f32,
+    },
+    /// An arc that sweeps from one side to the other across the zone's lifetime.
+    SweepingArc {
+        radius: f32,
+        angle: f32,
+    },
+    /// A line along X with rounded ends.
+    Capsule {
+        length: f32,
+        radius: f32,
+    },
+    Ring {
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+    Compound(Vec<Hitbox>),
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct Hitbox {
+    pub shape: HitboxShape,
+    /// Offset of the shape's center from the damage zone.
+    pub offset: Vec2,
+}
+
+impl Hitbox {
+    pub fn new(shape: HitboxShape, offset: Vec2) -> Self {
+        Hitbox { shape, offset }
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Hitbox::new(HitboxShape::Circle { radius }, Vec2::ZERO)
+    }
+
+    /// Whether the shape changes across the zone's lifetime.
+    pub fn is_sweeping(&self) -> bool {
+        match &self.shape {
+            HitboxShape::SweepingArc { .. } => true,
+            HitboxShape::Compound(hitboxes) => hitboxes.iter().any(Hitbox::is_sweeping),
+            _ => false,
+        }
+    }
+
+    /// Distance from the damage zone to the farthest point of the shape.
+    pub fn radius(&self) -> f32 {
+        let shape_radius = match &self.shape {
+            HitboxShape::Circle { radius }
+            | HitboxShape::Arc { radius, .. }
+            | HitboxShape::SweepingArc { radius, .. } => *radius,
+            HitboxShape::Capsule { length, radius } => length / 2.0 + radius,
+            HitboxShape::Ring { outer_radius, .. } => *outer_radius,
+            HitboxShape::Compound(hitboxes) => {
+                hitboxes.iter().map(Hitbox::radius).fold(0.0, f32::max)
+            }
+        };
+        self.offset.length() + shape_radius
+    }
+
+    /// Grow or shrink the shape and its offset.
+    pub fn scale(&mut self, factor: f32) {
+        self.offset *= factor;
+        match &mut self.shape {
+            HitboxShape::Circle { radius }
+            | HitboxShape::Arc { radius, .. }
+            | HitboxShape::SweepingArc { radius, .. } => *radius *= factor,
+            HitboxShape::Capsule { length, radius } => {
+                *length *= factor;
+                *radius *= factor;
+            }
+            HitboxShape::Ring {
+                inner_radius,
+                outer_radius,
+            } => {
+                *inner_radius *= factor;
+                *outer_radius *= factor;
+            }
+            HitboxShape::Compound(hitboxes) => {
+                for hitbox in hitboxes {
+                    hitbox.scale(factor);
+                }
+            }
+        }
+    }
+
+    /// The collider of the hitbox, `progress` of the way through the zone's lifetime.
+    pub fn collider(&self, progress: f32) -> Collider {
+        let mut parts = Vec::new();
+        self.add_parts(Vec2::ZERO, progress.clamp(0.0, 1.0), &mut parts);
+        // Compound colliders can't be empty, e.g. for an arc that hasn't started sweeping.
+        if parts.is_empty() {
+            parts.push((self.offset, 0.0, Collider::circle(0.0)));
+        }
+        Collider::compound(parts)
+    }
+
+    /// Compound colliders can't be nested, so every shape adds its primitive parts.
+    fn add_parts(&self, origin: Vec2, progress: f32, parts: &mut Vec<(Vec2, f32, Collider)>) {
+        let center = origin + self.offset;
+        match &self.shape {
+            HitboxShape::Circle { radius } => parts.push((center, 0.0, Collider::circle(*radius))),
+            HitboxShape::Arc { radius, angle } => {
+                add_arc_parts(center, *radius, -angle / 2.0, angle / 2.0, parts);
+            }
+            HitboxShape::SweepingArc { radius, angle } => {
+                let start = -angle / 2.0;
+                add_arc_parts(center, *radius, start, start + angle * progress, parts);
+            }
+            HitboxShape::Capsule { length, radius } => parts.push((
+                center,
+                0.0,
+                Collider::capsule_endpoints(
+                    *radius,
+                    Vec2::new(-length / 2.0, 0.0),
+                    Vec2::new(length / 2.0, 0.0),
+                ),
+            )),
+            HitboxShape::Ring {
+                inner_radius,
+                outer_radius,
+            } => {
+                let segments = (TAU / SEGMENT_ANGLE).ceil() as usize;
+                for i in 0..segments {
+                    let from = Vec2::from_angle(i as f32 * SEGMENT_ANGLE);
+                    let to = Vec2::from_angle((i + 1) as f32 * SEGMENT_ANGLE);
+                    let quad = vec![
+                        from * *inner_radius,
+                        from * *outer_radius,
+                        to * *outer_radius,
+                        to * *inner_radius,
+                    ];
+                    if let Some(collider) = Collider::convex_hull(quad) {
+                        parts.push((center, 0.0, collider));
+                    }
+                }
+            }
+            HitboxShape::Compound(hitboxes) => {
+                for hitbox in hitboxes {
+                    hitbox.add_parts(center, progress, parts);
+                }
+            }
+        }
+    }
+}
+
+/// A fan of triangles, so arcs wider than half a turn work too.
+fn add_arc_parts(
+    center: Vec2,
+    radius: f32,
+    from: f32,
+    to: f32,
+    parts: &mut Vec<(Vec2, f32, Collider)>,
+) {
+    let span = to - from;
+    if span <= 0.0 {
+        return;
+    }
+    let segments = (span / SEGMENT_ANGLE).ceil().max(1.0) as usize;
+    let step = span / segments as f32;
+    for i in 0..segments {
+        let a = Vec2::from_angle(from + i as f32 * step) * radius;
+        let b = Vec2::from_angle(from + (i + 1) as f32 * step) * radius;
+        parts.push((center, 0.0, Collider::triangle(Vec2::ZERO, a, b)));
+    }
+}
@@ -11,6 +11,7 @@ use bevy::{
     input::gamepad::GamepadEvent, input::keyboard::KeyboardInput, prelude::*, window::PrimaryWindow,
 };
 use leafwing_input_manager::{axislike::DualAxisData, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use super::spawn::player::Player;
 
@@ -19,7 +20,6 @@ pub(super) fn plugin(app: &mut App) {
         // Defined below, detects whether MKB or gamepad are active
         .add_plugins(InputModeManagerPlugin)
         .init_resource::<ActionState<PlayerAction>>()
-        .insert_resource(PlayerAction::default_input_map())
         // Set up the input processing
         .add_systems(
             Update,
@@ -30,7 +30,7 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 // ----------------------------- Player Action Input Handling -----------------------------
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect, Serialize, Deserialize)]
 pub enum PlayerAction {
     Move,
     Look,
@@ -41,11 +41,32 @@ pub enum PlayerAction {
     AbilityPrimary,
     AbilitySeconary,
     SwitchWeapon,
+    Block,
 }
 
 impl PlayerAction {
+    /// Every action, in the order they are listed in the settings.
+    pub const ALL: [Self; 10] = [
+        Self::Move,
+        Self::Look,
+        Self::Dash,
+        Self::Interact,
+        Self::AttackPrimary,
+        Self::AttackSecondary,
+        Self::AbilityPrimary,
+        Self::AbilitySeconary,
+        Self::SwitchWeapon,
+        Self::Block,
+    ];
+
+    /// Whether the action is bound to buttons rather than sticks.
+    /// The others are rebound one direction of their keyboard dpad at a time.
+    pub fn is_button(self) -> bool {
+        !matches!(self, Self::Move | Self::Look)
+    }
+
     /// Define the default binding to the input
-    fn default_input_map() -> InputMap<Self> {
+    pub fn default_input_map() -> InputMap<Self> {
         let mut input_map = InputMap::default();
 
         // Default gamepad input bindings
@@ -55,6 +76,10 @@ impl PlayerAction {
         input_map.insert(Self::Interact, GamepadButtonType::West);
         input_map.insert(Self::AttackPrimary, GamepadButtonType::LeftTrigger2);
         input_map.insert(Self::AttackSecondary, GamepadButtonType::RightTrigger2);
+        input_map.insert(Self::AbilityPrimary, GamepadButtonType::LeftTrigger);
+        input_map.insert(Self::AbilitySeconary, GamepadButtonType::RightTrigger);
+        input_map.insert(Self::SwitchWeapon, GamepadButtonType::North);
+        input_map.insert(Self::Block, GamepadButtonType::East);
 
         // Default kbm input bindings
         input_map.insert(Self::Move, VirtualDPad::wasd());
@@ -63,6 +88,10 @@ impl PlayerAction {
         input_map.insert(Self::Interact, KeyCode::KeyE);
         input_map.insert(Self::AttackPrimary, MouseButton::Left);
         input_map.insert(Self::AttackSecondary, MouseButton::Right);
+        input_map.insert(Self::AbilityPrimary, KeyCode::KeyR);
+        input_map.insert(Self::AbilitySeconary, KeyCode::KeyF);
+        input_map.insert(Self::SwitchWeapon, KeyCode::KeyQ);
+        input_map.insert(Self::Block, KeyCode::ShiftLeft);
 
         input_map
     }
@@ -0,0 +1,172 @@
+//! Push entities away from whoever hit them, and stun them for a moment.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    ability::AbilityController,
+    attack::AttackController,
+    block::BlockController,
+    dash::{self, DashController},
+    death::Dying,
+    movement::Movement,
+};
+use crate::AppSet;
+
+/// How fast knockback velocity decays, per second.
+const KNOCKBACK_DAMPING: f32 = 8.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<KnockbackEvent>();
+    app.register_type::<KnockbackResistance>();
+    app.register_type::<Hitstun>();
+    app.add_systems(
+        Update,
+        (
+            tick_hitstun.in_set(AppSet::TickTimers),
+            suppress_hitstunned_intent.in_set(AppSet::ConstrainInput),
+            // `apply_dash` gives control back to entities that aren't dashing,
+            // which would undo the knockback until `Hitstun` is inserted.
+            apply_knockback_events
+                .after(dash::apply_dash)
+                .in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// The knockback a hit inflicts, pushing the target away from the hit's source.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct Knockback {
+    /// Initial speed of the target.
+    pub strength: f32,
+    /// How long the target loses control of its movement.
+    pub hitstun: Duration,
+}
+
+/// Fraction of incoming knockback strength and hitstun that is ignored.
+/// `1.0` makes the entity immovable.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct KnockbackResistance(pub f32);
+
+/// An entity that is being knocked back and can't act.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hitstun {
+    pub remaining: Duration,
+}
+
+/// Sent when a hit carrying [`Knockback`] lands.
+#[derive(Event)]
+pub struct KnockbackEvent {
+    pub knockback: Knockback,
+    pub target: Entity,
+    pub source: Entity,
+}
+
+fn apply_knockback_events(
+    mut commands: Commands,
+    mut events: EventReader<KnockbackEvent>,
+    transform_query: Query<&Transform>,
+    mut target_query: Query<
+        (
+            &mut LinearVelocity,
+            Option<&mut Movement>,
+            Option<&KnockbackResistance>,
+            Option<&mut Hitstun>,
+        ),
+        Without<Dying>,
+    >,
+) {
+    for &KnockbackEvent {
+        knockback,
+        target,
+        source,
+    } in events.read()
+    {
+        let Ok([target_transform, source_transform]) = transform_query.get_many([target, source])
+        else {
+            continue;
+        };
+        let Ok((mut linear_velocity, movement, resistance, hitstun)) = target_query.get_mut(target)
+        else {
+            continue;
+        };
+
+        let factor = 1.0 - resistance.map_or(0.0, |r| r.0.clamp(0.0, 1.0));
+        if factor <= 0.0 {
+            continue;
+        }
+        let direction = (target_transform.translation - source_transform.translation)
+            .xy()
+            .try_normalize()
+            .unwrap_or(Vec2::X);
+        linear_velocity.0 = direction * knockback.strength * factor;
+
+        let duration = knockback.hitstun.mul_f32(factor);
+        if let Some(mut movement) = movement {
+            movement.toggle_control(false);
+        }
+        match hitstun {
+            Some(mut hitstun) => hitstun.remaining = hitstun.remaining.max(duration),
+            None => {
+                commands.entity(target).insert(Hitstun {
+                    remaining: duration,
+                });
+            }
+        }
+    }
+}
+
+fn tick_hitstun(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut Hitstun,
+        &mut LinearVelocity,
+        Option<&mut Movement>,
+    )>,
+) {
+    for (entity, mut hitstun, mut linear_velocity, movement) in &mut query {
+        hitstun.remaining = hitstun.remaining.saturating_sub(time.delta());
+        linear_velocity.0 *= f32::max(1.0 - KNOCKBACK_DAMPING * time.delta_seconds(), 0.0);
+        if !hitstun.remaining.is_zero() {
+            continue;
+        }
+
+        if let Some(mut movement) = movement {
+            movement.toggle_control(true);
+        }
+        commands.entity(entity).remove::<Hitstun>();
+    }
+}
+
+fn suppress_hitstunned_intent(
+    mut query: Query<
+        (
+            Option<&mut AttackController>,
+            Option<&mut DashController>,
+            Option<&mut AbilityController>,
+            Option<&mut BlockController>,
+        ),
+        With<Hitstun>,
+    >,
+) {
+    for (attack, dash, ability, block) in &mut query {
+        if let Some(mut attack) = attack {
+            attack.clear_intent();
+        }
+        if let Some(mut dash) = dash {
+            dash.intent = false;
+        }
+        if let Some(mut ability) = ability {
+            ability.intent = None;
+        }
+        if let Some(mut block) = block {
+            block.intent = false;
+        }
+    }
+}
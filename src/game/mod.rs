@@ -3,20 +3,39 @@
 use avian2d::{prelude::*, PhysicsPlugins};
 use bevy::prelude::*;
 
+pub mod ability;
+mod afterimage;
 mod animation;
 pub mod assets;
 pub mod attack;
 pub mod audio;
 pub mod behaviour;
+pub mod bindings;
+pub mod block;
+pub mod cancel;
+pub mod charge;
+pub mod combat_log;
+pub mod combo;
+pub mod damage_modifiers;
 pub mod damage_zone;
 mod damaging_contacts;
 pub mod dash;
+pub mod death;
 pub mod health;
+pub mod hitbox;
 pub mod input;
 mod kinematic_controller_collisions;
+pub mod knockback;
 mod movement;
+pub mod projectile;
+#[cfg(not(target_family = "wasm"))]
+mod replay;
+pub mod rng;
 pub mod spawn;
+pub mod status_effect;
+pub mod time_dilation;
 pub mod ui;
+pub mod weapon;
 
 #[derive(PhysicsLayer)]
 pub enum GameLayer {
@@ -24,24 +43,51 @@ pub enum GameLayer {
     PlayerHitbox,
     Enemies,
     LevelBounds,
+    Projectiles,
+    Hazards,
 }
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(PhysicsPlugins::default().with_length_unit(10.0));
     app.add_plugins((
-        PhysicsPlugins::default().with_length_unit(10.0),
+        afterimage::plugin,
         animation::plugin,
         audio::plugin,
         assets::plugin,
-        attack::plugin,
-        damage_zone::plugin,
-        damaging_contacts::plugin,
-        dash::plugin,
         input::plugin,
         movement::plugin,
         spawn::plugin,
         behaviour::plugin,
-        health::plugin,
+        bindings::plugin,
         kinematic_controller_collisions::plugin,
+        rng::plugin,
+        time_dilation::plugin,
         ui::plugin,
     ));
+    // Replays are read from and written to files.
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugins(replay::plugin);
+    // Combat: what the player and enemies do.
+    app.add_plugins((
+        ability::plugin,
+        attack::plugin,
+        block::plugin,
+        cancel::plugin,
+        charge::plugin,
+        combo::plugin,
+        dash::plugin,
+        weapon::plugin,
+    ));
+    // Combat: how hits land and what they do.
+    app.add_plugins((
+        combat_log::plugin,
+        damage_modifiers::plugin,
+        damage_zone::plugin,
+        damaging_contacts::plugin,
+        death::plugin,
+        health::plugin,
+        knockback::plugin,
+        projectile::plugin,
+        status_effect::plugin,
+    ));
 }
@@ -3,7 +3,10 @@
 //! If you want to move the player in a smoother way,
 //! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/latest/examples/movement/physics_in_fixed_timestep.rs).
 
-use super::input::PlayerAction;
+use super::{
+    ability::Buffs, block::Block, charge::Charging, death::Dying, input::PlayerAction,
+    status_effect::StatusEffects,
+};
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
@@ -78,11 +81,28 @@ impl Movement {
 }
 
 fn apply_movement(
-    mut movement_query: Query<(&MovementController, &Movement, &mut LinearVelocity)>,
+    mut movement_query: Query<
+        (
+            &MovementController,
+            &Movement,
+            &mut LinearVelocity,
+            Option<&StatusEffects>,
+            Option<&Charging>,
+            Option<&Buffs>,
+            Option<&Block>,
+        ),
+        Without<Dying>,
+    >,
 ) {
-    for (controller, movement, mut linear_velocity) in &mut movement_query {
+    for (controller, movement, mut linear_velocity, status_effects, charging, buffs, block) in
+        &mut movement_query
+    {
         if movement.controls_rigid_body {
-            let velocity = movement.speed * controller.0;
+            let speed_multiplier = status_effects.map_or(1.0, |s| s.speed_multiplier())
+                * charging.map_or(1.0, |c| c.move_speed_multiplier())
+                * buffs.map_or(1.0, |b| b.move_speed_multiplier())
+                * block.map_or(1.0, |b| b.move_speed_multiplier());
+            let velocity = movement.speed * speed_multiplier * controller.0;
             linear_velocity.0 = velocity;
         }
     }
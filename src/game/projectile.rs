@@ -0,0 +1,355 @@
+//! Projectiles that fly, pierce, bounce off the level bounds and home in on targets.
+//! Parried projectiles are reflected back at whoever is on the side of their source.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    block::{Block, BlockEvent, Guard},
+    damage_zone::{in_stable_order, DamageZoneBundle, DamagedEntities},
+    death::Dying,
+    health::{DamageEvent, DamageKind, Health},
+    hitbox::Hitbox,
+    knockback::Knockback,
+    status_effect::InflictsStatusEffect,
+    GameLayer,
+};
+use crate::AppSet;
+
+/// How far homing projectiles look for a target.
+const HOMING_RANGE: f32 = 300.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Projectile>();
+    app.add_systems(
+        Update,
+        (steer_homing_projectiles, update_projectiles)
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+/// Something that happens where a projectile hits or expires.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub enum ProjectileEffect {
+    /// Spawn a damage zone, e.g. an explosion.
+    DamageZone {
+        damage: f32,
+        kind: DamageKind,
+        radius: f32,
+        lifetime: Duration,
+    },
+}
+
+/// How a projectile moves and behaves. Its damage comes from whoever fires it.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct ProjectileDefinition {
+    pub speed: f32,
+    pub radius: f32,
+    pub lifetime: Duration,
+    /// How many targets it passes through before being destroyed.
+    pub pierce: u32,
+    /// How many times it bounces off the level bounds before being destroyed.
+    pub bounces: u32,
+    /// How fast it turns towards the closest target, in radians per second.
+    pub homing: f32,
+    pub on_hit: Option<ProjectileEffect>,
+    pub on_expire: Option<ProjectileEffect>,
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Projectile {
+    source: Entity,
+    damage: f32,
+    kind: DamageKind,
+    knockback: Option<Knockback>,
+    definition: ProjectileDefinition,
+    own_layer: LayerMask,
+    /// Layers of the entities the projectile can hit.
+    targets: LayerMask,
+    lifetime: Duration,
+    pierce: u32,
+    bounces: u32,
+}
+
+/// A projectile, without visuals or a transform. Spawn it with both.
+#[derive(Bundle)]
+pub struct ProjectileBundle {
+    pub projectile: Projectile,
+    pub rigid_body: RigidBody,
+    pub linear_velocity: LinearVelocity,
+    pub collider: Collider,
+    pub layers: CollisionLayers,
+    pub sensor: Sensor,
+    pub damaged_entities: DamagedEntities,
+}
+
+impl ProjectileBundle {
+    pub fn new(
+        source: Entity,
+        damage: f32,
+        kind: DamageKind,
+        definition: ProjectileDefinition,
+        direction: Vec2,
+        own_layer: impl Into<LayerMask>,
+        collides_with_layers: impl Into<LayerMask>,
+    ) -> ProjectileBundle {
+        let own_layer = own_layer.into();
+        let targets = collides_with_layers.into();
+        ProjectileBundle {
+            projectile: Projectile {
+                source,
+                damage,
+                kind,
+                knockback: None,
+                definition,
+                own_layer,
+                targets,
+                lifetime: definition.lifetime,
+                pierce: definition.pierce,
+                bounces: definition.bounces,
+            },
+            rigid_body: RigidBody::Kinematic,
+            linear_velocity: LinearVelocity(
+                direction.try_normalize().unwrap_or(Vec2::X) * definition.speed,
+            ),
+            collider: Collider::circle(definition.radius),
+            layers: projectile_layers(own_layer, targets),
+            sensor: Sensor,
+            damaged_entities: DamagedEntities::default(),
+        }
+    }
+
+    pub fn with_knockback(mut self, knockback: Knockback) -> Self {
+        self.projectile.knockback = Some(knockback);
+        self
+    }
+}
+
+/// Projectiles also bounce off the level bounds.
+fn projectile_layers(own_layer: LayerMask, targets: LayerMask) -> CollisionLayers {
+    CollisionLayers::new(
+        own_layer | LayerMask::from(GameLayer::Projectiles),
+        targets | LayerMask::from(GameLayer::LevelBounds),
+    )
+}
+
+/// Turn homing projectiles towards the closest target in range.
+fn steer_homing_projectiles(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut query: Query<(
+        &Projectile,
+        &Transform,
+        &DamagedEntities,
+        &mut LinearVelocity,
+        &mut Rotation,
+    )>,
+    collider_parents: Query<&ColliderParent>,
+    target_query: Query<&Transform, (With<Health>, Without<Dying>)>,
+) {
+    for (projectile, transform, damaged_entities, mut linear_velocity, mut rotation) in &mut query {
+        let position = transform.translation.xy();
+        if projectile.definition.homing > 0.0 {
+            let closest_target = spatial_query
+                .shape_intersections(
+                    &Collider::circle(HOMING_RANGE),
+                    position,
+                    0.0,
+                    SpatialQueryFilter::from_mask(projectile.targets),
+                )
+                .into_iter()
+                .map(|collider| collider_parents.get(collider).map_or(collider, |p| p.get()))
+                .filter(|target| !damaged_entities.contains_key(target))
+                .filter_map(|target| target_query.get(target).ok())
+                .map(|target_transform| target_transform.translation.xy() - position)
+                .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+            if let Some(to_target) = closest_target {
+                let max_turn = projectile.definition.homing * time.delta_seconds();
+                let turn = linear_velocity
+                    .angle_between(to_target)
+                    .clamp(-max_turn, max_turn);
+                linear_velocity.0 = Vec2::from_angle(turn).rotate(linear_velocity.0);
+            }
+        }
+        *rotation = Rotation::radians(linear_velocity.to_angle());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_projectiles(
+    mut commands: Commands,
+    mut events: EventWriter<DamageEvent>,
+    mut block_events: EventWriter<BlockEvent>,
+    time: Res<Time>,
+    collisions: Res<Collisions>,
+    mut query: Query<(
+        Entity,
+        &mut Projectile,
+        &Transform,
+        &Rotation,
+        &CollidingEntities,
+        &mut DamagedEntities,
+        &mut LinearVelocity,
+        &mut CollisionLayers,
+        Option<&InflictsStatusEffect>,
+        Option<&Name>,
+    )>,
+    layers_query: Query<&CollisionLayers, Without<Projectile>>,
+    collider_parents: Query<&ColliderParent>,
+    damageable_query: Query<(), With<Health>>,
+    block_query: Query<(&Block, &Transform)>,
+    transform_query: Query<&GlobalTransform>,
+) {
+    for (
+        entity,
+        mut projectile,
+        transform,
+        rotation,
+        colliding_entities,
+        mut damaged_entities,
+        mut linear_velocity,
+        mut layers,
+        inflicts_status_effect,
+        name,
+    ) in &mut query
+    {
+        let position = transform.translation.xy();
+        let expire = |commands: &mut Commands, projectile: &Projectile| {
+            if let Some(effect) = projectile.definition.on_expire {
+                spawn_effect(commands, projectile, effect, position);
+            }
+            commands.entity(entity).despawn_recursive();
+        };
+
+        projectile.lifetime = projectile.lifetime.saturating_sub(time.delta());
+        if projectile.lifetime.is_zero() {
+            expire(&mut commands, &projectile);
+            continue;
+        }
+
+        let mut destroyed = false;
+        for colliding_entity in in_stable_order(colliding_entities, &transform_query) {
+            let is_level_bounds = layers_query
+                .get(colliding_entity)
+                .is_ok_and(|layers| layers.memberships.has_all(GameLayer::LevelBounds));
+            if is_level_bounds {
+                let Some(contacts) = collisions.get(entity, colliding_entity) else {
+                    continue;
+                };
+                for manifold in &contacts.manifolds {
+                    // Points from the projectile towards the level bounds.
+                    let normal = if contacts.entity1 == entity {
+                        manifold.global_normal1(rotation)
+                    } else {
+                        manifold.global_normal2(rotation)
+                    };
+                    if linear_velocity.dot(normal) <= 0.0 {
+                        continue;
+                    }
+                    if projectile.bounces == 0 {
+                        expire(&mut commands, &projectile);
+                        destroyed = true;
+                        break;
+                    }
+                    projectile.bounces -= 1;
+                    let velocity = linear_velocity.0;
+                    linear_velocity.0 = velocity - 2.0 * velocity.dot(normal) * normal;
+                }
+            } else {
+                // Hitboxes can be children of the entity that has health.
+                let target = collider_parents
+                    .get(colliding_entity)
+                    .map_or(colliding_entity, |p| p.get());
+                if target == projectile.source
+                    || damaged_entities.contains_key(&target)
+                    || !damageable_query.contains(target)
+                {
+                    continue;
+                }
+                let parried = block_query
+                    .get(target)
+                    .is_ok_and(|(block, target_transform)| {
+                        block.guard(position - target_transform.translation.xy())
+                            == Some(Guard::Parried)
+                    });
+                if parried {
+                    block_events.send(BlockEvent {
+                        target,
+                        source: projectile.source,
+                        guard: Guard::Parried,
+                    });
+                    // Send it back, now hitting the side it came from.
+                    let own_layer = layers_query
+                        .get(colliding_entity)
+                        .map_or(projectile.targets, |layers| layers.memberships);
+                    projectile.targets = projectile.own_layer;
+                    projectile.own_layer = own_layer;
+                    projectile.source = target;
+                    *layers = projectile_layers(own_layer, projectile.targets);
+                    linear_velocity.0 = -linear_velocity.0;
+                    damaged_entities.clear();
+                    continue;
+                }
+                damaged_entities.insert(target, time.elapsed());
+                events.send(DamageEvent {
+                    status_effect: inflicts_status_effect.map(|i| i.0),
+                    knockback: projectile.knockback,
+                    origin: Some(position),
+                    attack: name.cloned(),
+                    ..DamageEvent::new(
+                        projectile.damage,
+                        projectile.kind,
+                        target,
+                        projectile.source,
+                    )
+                });
+                if let Some(effect) = projectile.definition.on_hit {
+                    spawn_effect(&mut commands, &projectile, effect, position);
+                }
+                if projectile.pierce == 0 {
+                    commands.entity(entity).despawn_recursive();
+                    destroyed = true;
+                } else {
+                    projectile.pierce -= 1;
+                }
+            }
+            if destroyed {
+                break;
+            }
+        }
+    }
+}
+
+fn spawn_effect(
+    commands: &mut Commands,
+    projectile: &Projectile,
+    effect: ProjectileEffect,
+    position: Vec2,
+) {
+    match effect {
+        ProjectileEffect::DamageZone {
+            damage,
+            kind,
+            radius,
+            lifetime,
+        } => {
+            commands.spawn((
+                Name::new("ProjectileDamageZone"),
+                DamageZoneBundle::new(
+                    projectile.source,
+                    damage,
+                    kind,
+                    lifetime,
+                    Hitbox::circle(radius),
+                    projectile.own_layer,
+                    projectile.targets,
+                ),
+                TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            ));
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Record runs and play them back, to reproduce combat bugs.
+//!
+//! Every run is recorded to [`REPLAY_PATH`]: the run's RNG seed, and for every frame the
+//! time it took and the player's [`ActionState`], including the look direction derived from
+//! the mouse. Playback feeds those back in place of the player's inputs, and steps time by
+//! the recorded frame times instead of the wall clock.
+//!
+//! - `--replay <path>` plays a replay back.
+//! - `--verify <path>` plays it back, compares the end of the run against the recording
+//!   and exits with an error if they differ.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use leafwing_input_manager::{action_state::ActionData, buttonlike::ButtonState, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    health::Health,
+    input::PlayerAction,
+    rng::{seed_run, GameRng},
+};
+use crate::{screen::Screen, AppSet};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Replay::from_args());
+    app.add_systems(OnEnter(Screen::Title), start_playback);
+    app.add_systems(OnEnter(Screen::Playing), start_run.after(seed_run));
+    app.add_systems(
+        Update,
+        (record_frame, play_back_frame)
+            .after(AppSet::PrepareInput)
+            .before(AppSet::RecordInput)
+            .run_if(in_state(Screen::Playing)),
+    );
+    // Once the frame is over, physics included.
+    app.add_systems(Last, hash_frame.run_if(in_state(Screen::Playing)));
+    app.add_systems(OnExit(Screen::Playing), end_run);
+}
+
+const REPLAY_PATH: &str = "replay.ron";
+
+#[derive(Serialize, Deserialize, Default)]
+struct ReplayFile {
+    seed: u64,
+    frames: Vec<ReplayFrame>,
+    /// Hash of the game state at the end of the last frame.
+    end_hash: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayFrame {
+    /// Real time since the previous frame.
+    delta: Duration,
+    /// Actions that are not simply released.
+    actions: Vec<(PlayerAction, ActionData)>,
+}
+
+struct Playback {
+    replay: ReplayFile,
+    next_frame: usize,
+    /// Exit once the replay is over, with an error if the run diverged.
+    verify: bool,
+}
+
+#[derive(Resource)]
+enum Replay {
+    /// The current run is not recorded.
+    Idle,
+    Recording(ReplayFile),
+    Playback(Playback),
+}
+
+impl Replay {
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let verify = match arg.as_str() {
+                "--replay" => false,
+                "--verify" => true,
+                _ => continue,
+            };
+            let Some(path) = args.next() else {
+                warn!("{arg} needs the path of a replay");
+                break;
+            };
+            match std::fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|replay| ron::from_str(&replay).map_err(|error| error.to_string()))
+            {
+                Ok(replay) => {
+                    return Replay::Playback(Playback {
+                        replay,
+                        next_frame: 0,
+                        verify,
+                    })
+                }
+                Err(error) => warn!("Could not load replay {path}: {error}"),
+            }
+        }
+        Replay::Idle
+    }
+}
+
+/// Skips the title screen when there is a replay to play back.
+fn start_playback(
+    replay: Res<Replay>,
+    mut rng: ResMut<GameRng>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    let Replay::Playback(playback) = &*replay else {
+        return;
+    };
+    let Some(first_frame) = playback.replay.frames.first() else {
+        warn!("The replay has no frames");
+        return;
+    };
+    info!("Playing back {} frames", playback.replay.frames.len());
+    rng.next_seed = Some(playback.replay.seed);
+    // The run starts on the next frame.
+    *time_update = TimeUpdateStrategy::ManualDuration(first_frame.delta);
+    next_screen.set(Screen::Playing);
+}
+
+fn start_run(
+    mut replay: ResMut<Replay>,
+    rng: Res<GameRng>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    // Physics time left over from before the run would shift when physics steps.
+    if let TimestepMode::Fixed { overstep, .. } = physics_time.timestep_mode_mut() {
+        *overstep = Duration::ZERO;
+    }
+    if !matches!(*replay, Replay::Playback(_)) {
+        *replay = Replay::Recording(ReplayFile {
+            seed: rng.seed(),
+            ..default()
+        });
+    }
+}
+
+fn record_frame(
+    real_time: Res<Time<Real>>,
+    action_state: Res<ActionState<PlayerAction>>,
+    mut replay: ResMut<Replay>,
+) {
+    let Replay::Recording(recording) = &mut *replay else {
+        return;
+    };
+    let actions = PlayerAction::ALL
+        .into_iter()
+        .filter_map(|action| {
+            action_state
+                .action_data(&action)
+                .filter(|data| data.state != ButtonState::Released || data.axis_pair.is_some())
+                .map(|data| (action, data.clone()))
+        })
+        .collect();
+    recording.frames.push(ReplayFrame {
+        delta: real_time.delta(),
+        actions,
+    });
+}
+
+/// Replaces the player's inputs with the recorded ones.
+fn play_back_frame(
+    mut replay: ResMut<Replay>,
+    mut action_state: ResMut<ActionState<PlayerAction>>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+) {
+    let Replay::Playback(playback) = &mut *replay else {
+        return;
+    };
+    let Some(frame) = playback.replay.frames.get(playback.next_frame) else {
+        return;
+    };
+    *action_state = ActionState::default();
+    for (action, data) in &frame.actions {
+        action_state.set_action_data(*action, data.clone());
+    }
+    playback.next_frame += 1;
+    if let Some(next_frame) = playback.replay.frames.get(playback.next_frame) {
+        *time_update = TimeUpdateStrategy::ManualDuration(next_frame.delta);
+    }
+}
+
+fn hash_frame(
+    mut replay: ResMut<Replay>,
+    rng: Res<GameRng>,
+    health_query: Query<(&Name, &Transform, &Health)>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    match &mut *replay {
+        Replay::Idle => {}
+        Replay::Recording(recording) => {
+            recording.end_hash = state_hash(recording.frames.len(), &rng, &health_query);
+        }
+        Replay::Playback(playback) => {
+            if playback.next_frame < playback.replay.frames.len() {
+                return;
+            }
+            let hash = state_hash(playback.next_frame, &rng, &health_query);
+            let matches = hash == playback.replay.end_hash;
+            if matches {
+                info!("The replay matches the recorded run");
+            } else {
+                warn!(
+                    "The replay diverged from the recorded run: ended on {hash:016x} instead of {:016x}",
+                    playback.replay.end_hash
+                );
+            }
+            if playback.verify {
+                app_exit.send(if matches {
+                    AppExit::Success
+                } else {
+                    AppExit::error()
+                });
+            }
+            *time_update = TimeUpdateStrategy::Automatic;
+            *replay = Replay::Idle;
+            next_screen.set(Screen::Title);
+        }
+    }
+}
+
+/// Combines what combat bugs tend to change: where everything with health is, how much
+/// health it has, and how far the RNG has advanced.
+fn state_hash(
+    frames: usize,
+    rng: &GameRng,
+    health_query: &Query<(&Name, &Transform, &Health)>,
+) -> u64 {
+    // Entities don't get the same ids in the replay, so they are ordered by their state.
+    let mut entities: Vec<u64> = health_query
+        .iter()
+        .map(|(name, transform, health)| {
+            let mut hasher = DefaultHasher::new();
+            name.as_str().hash(&mut hasher);
+            for value in transform.translation.to_array() {
+                value.to_bits().hash(&mut hasher);
+            }
+            health.hit_points.to_bits().hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    entities.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    frames.hash(&mut hasher);
+    rng.peek_u64().hash(&mut hasher);
+    entities.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn end_run(
+    mut replay: ResMut<Replay>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    match std::mem::replace(&mut *replay, Replay::Idle) {
+        Replay::Idle => {}
+        Replay::Recording(recording) => {
+            let result = ron::to_string(&recording)
+                .map_err(|error| error.to_string())
+                .and_then(|saved| {
+                    std::fs::write(REPLAY_PATH, saved).map_err(|error| error.to_string())
+                });
+            match result {
+                Ok(()) => info!("Wrote {} frames to {REPLAY_PATH}", recording.frames.len()),
+                Err(error) => warn!("Could not write {REPLAY_PATH}: {error}"),
+            }
+        }
+        Replay::Playback(playback) => {
+            warn!(
+                "The replay stopped after {} of {} frames",
+                playback.next_frame,
+                playback.replay.frames.len()
+            );
+            *time_update = TimeUpdateStrategy::Automatic;
+            if playback.verify {
+                app_exit.send(AppExit::error());
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! The random number generator gameplay draws from.
+//!
+//! It is reseeded at the start of every run, so that a run can be replayed with the same rolls.
+//! Purely cosmetic randomness, like footstep sounds, keeps using `rand::thread_rng`.
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::screen::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(GameRng::new(rand::random()));
+    app.add_systems(OnEnter(Screen::Playing), seed_run);
+}
+
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    /// Used instead of a random seed for the next run, e.g. to replay it.
+    pub next_seed: Option<u64>,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            next_seed: None,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed of the current run.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The next number the generator gives, without advancing it.
+    pub fn peek_u64(&self) -> u64 {
+        self.rng.clone().next_u64()
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Runs before anything spawned for the run draws from the generator.
+pub fn seed_run(mut rng: ResMut<GameRng>) {
+    let seed = rng.next_seed.take().unwrap_or_else(rand::random);
+    *rng = GameRng::new(seed);
+}
@@ -0,0 +1,131 @@
+//! Spawn the hazards that hurt everything standing in them.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        damage_zone::DamageZoneBundle,
+        health::DamageKind,
+        hitbox::Hitbox,
+        status_effect::{InflictsStatusEffect, Stacking, StatusEffect, StatusEffectKind},
+        GameLayer,
+    },
+    screen::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(spawn_hazards);
+    app.register_type::<Hazard>();
+}
+
+#[derive(Event, Debug)]
+pub struct SpawnHazards;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct Hazard;
+
+struct HazardDefinition {
+    name: &'static str,
+    position: Vec2,
+    radius: f32,
+    color: Color,
+    damage: f32,
+    kind: DamageKind,
+    tick_interval: Duration,
+    /// Whether the ticks count as damage over time, or as separate hits.
+    damage_over_time: bool,
+    status_effect: Option<StatusEffect>,
+}
+
+fn spawn_hazards(_trigger: Trigger<SpawnHazards>, mut commands: Commands) {
+    let hazards = [
+        // Light damage, but poisons everything inside it
+        HazardDefinition {
+            name: "PoisonCloud",
+            position: Vec2::new(-350.0, -180.0),
+            radius: 60.0,
+            color: Color::srgba(0.4, 0.8, 0.2, 0.35),
+            damage: 1.0,
+            kind: DamageKind::Poison,
+            tick_interval: Duration::from_millis(1000),
+            damage_over_time: true,
+            status_effect: Some(StatusEffect {
+                kind: StatusEffectKind::Poison,
+                duration: Duration::from_secs(2),
+                tick_interval: Duration::from_millis(500),
+                magnitude: 1.0,
+                stacking: Stacking::Refresh,
+            }),
+        },
+        // Sets everything inside it on fire
+        HazardDefinition {
+            name: "FirePool",
+            position: Vec2::new(0.0, -220.0),
+            radius: 45.0,
+            color: Color::srgba(1.0, 0.4, 0.1, 0.4),
+            damage: 3.0,
+            kind: DamageKind::Fire,
+            tick_interval: Duration::from_millis(500),
+            damage_over_time: true,
+            status_effect: Some(StatusEffect {
+                kind: StatusEffectKind::Burn,
+                duration: Duration::from_secs(3),
+                tick_interval: Duration::from_millis(500),
+                magnitude: 2.0,
+                stacking: Stacking::Intensity { max_stacks: 3 },
+            }),
+        },
+        // Only physical damage, but a lot of it
+        HazardDefinition {
+            name: "SpikeFloor",
+            position: Vec2::new(350.0, -180.0),
+            radius: 40.0,
+            color: Color::srgba(0.6, 0.6, 0.65, 0.5),
+            damage: 8.0,
+            kind: DamageKind::Physical,
+            tick_interval: Duration::from_millis(750),
+            damage_over_time: false,
+            status_effect: None,
+        },
+    ];
+
+    for hazard in hazards {
+        let entity = commands.spawn_empty().id();
+        // Hazards are their own emitter, so kills are attributed to them
+        let mut damage_zone = DamageZoneBundle::new(
+            entity,
+            hazard.damage,
+            hazard.kind,
+            Duration::ZERO,
+            Hitbox::circle(hazard.radius),
+            GameLayer::Hazards,
+            [GameLayer::PlayerHitbox, GameLayer::Enemies],
+        )
+        .with_tick_interval(hazard.tick_interval)
+        .with_infinite_lifetime();
+        if hazard.damage_over_time {
+            damage_zone = damage_zone.with_damage_over_time();
+        }
+        commands.entity(entity).insert((
+            Name::new(hazard.name),
+            Hazard,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: hazard.color,
+                    custom_size: Some(Vec2::splat(hazard.radius * 2.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(hazard.position.extend(0.0)),
+                ..default()
+            },
+            damage_zone,
+            StateScoped(Screen::Playing),
+        ));
+        if let Some(effect) = hazard.status_effect {
+            commands.entity(entity).insert(InflictsStatusEffect(effect));
+        }
+    }
+}
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 
 use crate::game::GameLayer;
 
-use super::{melee_enemy::SpawnMeleeEnemy, player::SpawnPlayer};
+use super::{hazard::SpawnHazards, melee_enemy::SpawnMeleeEnemy, player::SpawnPlayer};
 
 pub const LEVEL_HEIGHT: f32 = 700.0;
 const WALL_THICKNESS: f32 = 50.0;
@@ -59,7 +59,11 @@ fn spawn_level(
         Collider::rectangle(WALL_THICKNESS, WALL_THICKNESS),
         CollisionLayers::new(
             GameLayer::LevelBounds,
-            [GameLayer::Enemies, GameLayer::PlayerMovement],
+            [
+                GameLayer::Enemies,
+                GameLayer::PlayerMovement,
+                GameLayer::Projectiles,
+            ],
         ),
     ));
     // Floor
@@ -74,10 +78,15 @@ fn spawn_level(
         Collider::rectangle(WALL_THICKNESS, WALL_THICKNESS),
         CollisionLayers::new(
             GameLayer::LevelBounds,
-            [GameLayer::Enemies, GameLayer::PlayerMovement],
+            [
+                GameLayer::Enemies,
+                GameLayer::PlayerMovement,
+                GameLayer::Projectiles,
+            ],
         ),
     ));
 
     commands.trigger(SpawnPlayer);
     commands.trigger(SpawnMeleeEnemy);
+    commands.trigger(SpawnHazards);
 }
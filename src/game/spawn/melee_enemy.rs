@@ -1,5 +1,7 @@
 //! Spawn the melee enemies.
 
+use std::time::Duration;
+
 use avian2d::prelude::*;
 use bevy::prelude::*;
 
@@ -8,8 +10,11 @@ use crate::{
         animation::PlayerAnimation,
         assets::{HandleMap, ImageKey},
         behaviour::follow::FollowPlayer,
-        health::Health,
+        death::DeathBehaviour,
+        health::{Health, Resistances},
+        knockback::KnockbackResistance,
         movement::{Movement, MovementController},
+        status_effect::{InflictsStatusEffect, Stacking, StatusEffect, StatusEffectKind},
         ui::status_bar::definition::StatusBarDefinition,
         GameLayer,
     },
@@ -67,19 +72,38 @@ fn spawn_melee_enemies(
             },
             animation,
             StateScoped(Screen::Playing),
-            RigidBody::Dynamic,
-            Collider::circle(10.0),
-            LockedAxes::ROTATION_LOCKED,
-            CollisionLayers::new(
-                GameLayer::Enemies,
-                [
+            (
+                RigidBody::Dynamic,
+                Collider::circle(10.0),
+                LockedAxes::ROTATION_LOCKED,
+                CollisionLayers::new(
                     GameLayer::Enemies,
-                    GameLayer::LevelBounds,
-                    GameLayer::PlayerHitbox,
-                ],
+                    [
+                        GameLayer::Enemies,
+                        GameLayer::LevelBounds,
+                        GameLayer::PlayerHitbox,
+                        GameLayer::Hazards,
+                    ],
+                ),
+                KnockbackResistance(0.2),
+            ),
+            (
+                Health::new(100.0),
+                Resistances {
+                    physical: 0.1,
+                    fire: -0.5,
+                    ..default()
+                },
+                StatusBarDefinition::<Health>::default(),
+                DeathBehaviour::despawn_after(Duration::from_millis(400)),
             ),
-            Health::new(100.0),
-            StatusBarDefinition::<Health>::default(),
+            InflictsStatusEffect(StatusEffect {
+                kind: StatusEffectKind::Poison,
+                duration: Duration::from_secs(3),
+                tick_interval: Duration::from_secs(1),
+                magnitude: 1.0,
+                stacking: Stacking::Intensity { max_stacks: 3 },
+            }),
         ));
     }
 }
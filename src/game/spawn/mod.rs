@@ -4,10 +4,16 @@
 
 use bevy::prelude::*;
 
+pub mod hazard;
 pub mod level;
 pub mod melee_enemy;
 pub mod player;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, player::plugin, melee_enemy::plugin));
+    app.add_plugins((
+        level::plugin,
+        player::plugin,
+        melee_enemy::plugin,
+        hazard::plugin,
+    ));
 }
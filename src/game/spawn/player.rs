@@ -8,13 +8,26 @@ use bevy::prelude::*;
 
 use crate::{
     game::{
+        ability::{Abilities, AbilityController, AbilityDefinition, Qi},
+        afterimage::AfterimageTrail,
         animation::PlayerAnimation,
         assets::{HandleMap, ImageKey},
         attack::{Attack, AttackController},
+        block::{Block, BlockController, BlockState},
+        cancel::CancelRules,
+        combo::Combo,
+        damage_modifiers::DamageDealtModifiers,
         dash::{Dash, DashController},
-        health::Health,
+        death::DeathBehaviour,
+        health::{
+            Health, HitCooldowns, Invulnerable, Regeneration, RegenerationRate, Resistances, Shield,
+        },
+        knockback::{Knockback, KnockbackResistance},
         movement::{Movement, MovementController},
-        ui::status_bar::definition::StatusBarDefinition,
+        rng::GameRng,
+        time_dilation::SlowMotionOnDeath,
+        ui::status_bar::definition::{Size, StatusBarDefinition},
+        weapon::{WeaponDefinition, WeaponInventory},
         GameLayer,
     },
     screen::Screen,
@@ -50,11 +63,27 @@ pub struct PlayerAppearance {
     player_animation: PlayerAnimation,
 }
 
+#[derive(Bundle)]
+pub struct PlayerVitals {
+    health: Health,
+    resistances: Resistances,
+    invulnerable: Invulnerable,
+    hit_cooldowns: HitCooldowns,
+    health_bar: StatusBarDefinition<Health>,
+    regeneration: Regeneration,
+    shield: Shield,
+    shield_bar: StatusBarDefinition<Shield>,
+    knockback_resistance: KnockbackResistance,
+    death_behaviour: DeathBehaviour,
+    slow_motion_on_death: SlowMotionOnDeath,
+}
+
 fn spawn_player(
     _trigger: Trigger<SpawnPlayer>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut rng: ResMut<GameRng>,
 ) {
     // A texture atlas is a way to split one image with a grid into multiple sprites.
     // By attaching it to a [`SpriteBundle`] and providing an index, we can specify which section of the image we want to see.
@@ -64,7 +93,6 @@ fn spawn_player(
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
     let player_animation = PlayerAnimation::new();
 
-    let mut rng = rand::thread_rng();
     let redish = f32::lerp(0.3, 1.0, rng.gen());
     let greenish = f32::lerp(0.3, 1.0, rng.gen());
     let blueish = f32::lerp(0.0, 0.3, rng.gen());
@@ -100,19 +128,95 @@ fn spawn_player(
                     GameLayer::LevelBounds,
                 ),
             },
-            Health::new(200.0),
-            StatusBarDefinition::<Health>::default(),
-            DashController::new(),
-            Dash::new(
-                600.0,
-                Duration::from_millis(200),
-                Duration::from_millis(100),
-                Duration::new(2, 0),
+            PlayerVitals {
+                health: Health::new(200.0),
+                resistances: Resistances::default(),
+                invulnerable: Invulnerable::new(Duration::from_millis(300)),
+                hit_cooldowns: HitCooldowns::new(Duration::from_secs(1)),
+                health_bar: StatusBarDefinition::<Health>::default(),
+                regeneration: Regeneration::new(
+                    RegenerationRate::Flat(2.0),
+                    Duration::from_secs(3),
+                ),
+                shield: Shield::new(50.0),
+                shield_bar: StatusBarDefinition::<Shield> {
+                    offset: Vec3::new(0.0, 29.0, 10.0),
+                    foreground_color: Color::srgb(0.3, 0.6, 1.0),
+                    ..default()
+                },
+                knockback_resistance: KnockbackResistance(0.5),
+                death_behaviour: DeathBehaviour::corpse_after(Duration::from_secs(1)),
+                slow_motion_on_death: SlowMotionOnDeath {
+                    scale: 0.25,
+                    duration: Duration::from_millis(1500),
+                },
+            },
+            (
+                DashController::new(),
+                Dash::new(
+                    120.0,
+                    Duration::from_millis(200),
+                    Duration::from_millis(100),
+                    Duration::new(2, 0),
+                )
+                .with_charges(2)
+                .with_iframes(Duration::from_millis(20), Duration::from_millis(150))
+                .with_pass_through(GameLayer::Enemies),
+                AfterimageTrail::new(
+                    Duration::from_millis(30),
+                    Color::srgba(0.5, 0.8, 1.0, 0.6),
+                    Duration::from_millis(250),
+                ),
+                StatusBarDefinition::<Dash> {
+                    size: Size::new(30.0, 2.0),
+                    offset: Vec3::new(0.0, -22.0, 10.0),
+                    foreground_color: Color::srgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+                BlockController::default(),
+                Block {
+                    state: BlockState::Lowered,
+                    facing: Vec2::X,
+                    arc: 2.0,
+                    damage_multiplier: 0.3,
+                    parry_window: Duration::from_millis(150),
+                    move_speed_multiplier: 0.5,
+                    cooldown: Duration::from_millis(300),
+                    stagger: Knockback {
+                        strength: 400.0,
+                        hitstun: Duration::from_millis(800),
+                    },
+                },
             ),
-            Attack,
+            (
+                Abilities::new(
+                    AbilityDefinition::wind_step(),
+                    AbilityDefinition::fire_lotus(),
+                ),
+                AbilityController::default(),
+                Qi::new(100.0, 8.0),
+                StatusBarDefinition::<Qi> {
+                    size: Size::new(40.0, 3.0),
+                    offset: Vec3::new(0.0, 20.0, 10.0),
+                    foreground_color: Color::srgb(0.2, 0.9, 0.8),
+                    ..default()
+                },
+            ),
+            DamageDealtModifiers {
+                crit_chance: 0.1,
+                crit_multiplier: 2.0,
+                ..default()
+            },
+            WeaponInventory::new([
+                WeaponDefinition::sword(),
+                WeaponDefinition::spear(),
+                WeaponDefinition::talisman(),
+            ]),
+            (Attack::new(1.0), CancelRules::player()),
+            Combo::default(),
             AttackController {
                 look_direction: Vec2::X,
-                intent: false,
+                ..default()
             },
         ))
         .with_children(|parent| {
@@ -121,7 +225,14 @@ fn spawn_player(
                 PlayerHitBox,
                 SpatialBundle::default(),
                 Collider::circle(10.0),
-                CollisionLayers::new(GameLayer::PlayerHitbox, GameLayer::Enemies),
+                CollisionLayers::new(
+                    GameLayer::PlayerHitbox,
+                    [
+                        GameLayer::Enemies,
+                        GameLayer::Projectiles,
+                        GameLayer::Hazards,
+                    ],
+                ),
                 Sensor,
             ));
         });
@@ -0,0 +1,254 @@
+//! Timed effects on entities: damage over time, slows and stuns.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    ability::AbilityController,
+    attack::AttackController,
+    block::BlockController,
+    dash::DashController,
+    death::Dying,
+    health::{DamageEvent, DamageKind},
+    movement::MovementController,
+};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<ApplyStatusEffectEvent>();
+    app.register_type::<StatusEffects>();
+    app.register_type::<StatusImmunities>();
+    app.register_type::<InflictsStatusEffect>();
+    app.add_systems(
+        Update,
+        (
+            tick_status_effects.in_set(AppSet::TickTimers),
+            suppress_stunned_intent.in_set(AppSet::ConstrainInput),
+            apply_status_effect_events.in_set(AppSet::Update),
+        ),
+    );
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffectKind {
+    /// Poison damage over time.
+    Poison,
+    /// Fire damage over time.
+    Burn,
+    /// Physical damage over time.
+    Bleed,
+    /// Reduces movement speed by `magnitude`, a fraction between 0 and 1.
+    Slow,
+    /// Suppresses movement, attack and dash intent.
+    Stun,
+}
+
+impl StatusEffectKind {
+    /// The kind of damage dealt on each tick, if this effect deals damage over time.
+    fn damage_kind(&self) -> Option<DamageKind> {
+        match self {
+            StatusEffectKind::Poison => Some(DamageKind::Poison),
+            StatusEffectKind::Burn => Some(DamageKind::Fire),
+            StatusEffectKind::Bleed => Some(DamageKind::Physical),
+            StatusEffectKind::Slow | StatusEffectKind::Stun => None,
+        }
+    }
+}
+
+/// What happens when an effect is applied to an entity that already has one of the same kind.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    /// Restart the existing effect's duration.
+    Refresh,
+    /// Add a stack to the existing effect, multiplying its magnitude, and restart its duration.
+    Intensity { max_stacks: u32 },
+    /// Run the new effect alongside the existing one.
+    Independent,
+}
+
+/// The description of an effect, as inflicted by a hit.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub duration: Duration,
+    /// Time between two ticks of damage over time, ignored by other effects.
+    pub tick_interval: Duration,
+    /// Damage per tick, or slow fraction.
+    pub magnitude: f32,
+    pub stacking: Stacking,
+}
+
+/// An effect running on an entity.
+#[derive(Reflect, Clone, Debug)]
+pub struct ActiveStatusEffect {
+    pub effect: StatusEffect,
+    pub source: Entity,
+    pub stacks: u32,
+    pub remaining: Duration,
+    pub until_tick: Duration,
+}
+
+impl ActiveStatusEffect {
+    fn new(effect: StatusEffect, source: Entity) -> Self {
+        ActiveStatusEffect {
+            effect,
+            source,
+            stacks: 1,
+            remaining: effect.duration,
+            until_tick: effect.tick_interval,
+        }
+    }
+
+    fn magnitude(&self) -> f32 {
+        self.effect.magnitude * self.stacks as f32
+    }
+}
+
+/// The effects currently running on an entity.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct StatusEffects(pub Vec<ActiveStatusEffect>);
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: StatusEffect, source: Entity) {
+        let existing = self.0.iter_mut().find(|a| a.effect.kind == effect.kind);
+        match (effect.stacking, existing) {
+            (Stacking::Refresh, Some(active)) => {
+                active.remaining = effect.duration;
+            }
+            (Stacking::Intensity { max_stacks }, Some(active)) => {
+                active.stacks = u32::min(active.stacks + 1, max_stacks);
+                active.remaining = effect.duration;
+            }
+            _ => self.0.push(ActiveStatusEffect::new(effect, source)),
+        }
+    }
+
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.0.iter().any(|a| a.effect.kind == kind)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.has(StatusEffectKind::Stun)
+    }
+
+    /// Multiplier to apply to the movement speed, using the strongest slow only.
+    pub fn speed_multiplier(&self) -> f32 {
+        let slow = self
+            .0
+            .iter()
+            .filter(|a| a.effect.kind == StatusEffectKind::Slow)
+            .map(|a| a.magnitude())
+            .fold(0.0, f32::max);
+        1.0 - slow.clamp(0.0, 1.0)
+    }
+}
+
+/// The effect kinds an entity can't be afflicted by.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct StatusImmunities(pub Vec<StatusEffectKind>);
+
+/// Makes the hits of a damage zone or a contact-damaging entity inflict an effect.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct InflictsStatusEffect(pub StatusEffect);
+
+/// Send this event to apply an effect to an entity.
+#[derive(Event)]
+pub struct ApplyStatusEffectEvent {
+    pub effect: StatusEffect,
+    pub target: Entity,
+    pub source: Entity,
+}
+
+fn apply_status_effect_events(
+    mut commands: Commands,
+    mut events: EventReader<ApplyStatusEffectEvent>,
+    mut query: Query<(Option<&mut StatusEffects>, Option<&StatusImmunities>), Without<Dying>>,
+) {
+    for &ApplyStatusEffectEvent {
+        effect,
+        target,
+        source,
+    } in events.read()
+    {
+        let Ok((status_effects, immunities)) = query.get_mut(target) else {
+            continue;
+        };
+        if immunities.is_some_and(|i| i.0.contains(&effect.kind)) {
+            continue;
+        }
+
+        match status_effects {
+            Some(mut status_effects) => status_effects.apply(effect, source),
+            None => {
+                let mut status_effects = StatusEffects::default();
+                status_effects.apply(effect, source);
+                commands.entity(target).insert(status_effects);
+            }
+        }
+    }
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut events: EventWriter<DamageEvent>,
+    mut query: Query<(Entity, &mut StatusEffects), Without<Dying>>,
+) {
+    for (entity, mut status_effects) in &mut query {
+        for active in status_effects.0.iter_mut() {
+            active.remaining = active.remaining.saturating_sub(time.delta());
+
+            let Some(kind) = active.effect.kind.damage_kind() else {
+                continue;
+            };
+            if active.effect.tick_interval.is_zero() {
+                continue;
+            }
+            active.until_tick = active.until_tick.saturating_sub(time.delta());
+            if active.until_tick.is_zero() {
+                active.until_tick = active.effect.tick_interval;
+                events.send(DamageEvent {
+                    damage_over_time: true,
+                    attack: Some(Name::new(format!("{:?}", active.effect.kind))),
+                    ..DamageEvent::new(active.magnitude(), kind, entity, active.source)
+                });
+            }
+        }
+        status_effects.0.retain(|a| !a.remaining.is_zero());
+    }
+}
+
+fn suppress_stunned_intent(
+    mut query: Query<(
+        &StatusEffects,
+        Option<&mut MovementController>,
+        Option<&mut AttackController>,
+        Option<&mut DashController>,
+        Option<&mut AbilityController>,
+        Option<&mut BlockController>,
+    )>,
+) {
+    for (status_effects, movement, attack, dash, ability, block) in &mut query {
+        if !status_effects.is_stunned() {
+            continue;
+        }
+        if let Some(mut movement) = movement {
+            movement.0 = Vec2::ZERO;
+        }
+        if let Some(mut attack) = attack {
+            attack.clear_intent();
+        }
+        if let Some(mut dash) = dash {
+            dash.intent = false;
+        }
+        if let Some(mut ability) = ability {
+            ability.intent = None;
+        }
+        if let Some(mut block) = block {
+            block.intent = false;
+        }
+    }
+}
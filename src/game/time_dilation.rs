@@ -0,0 +1,154 @@
+//! Slow down or freeze gameplay time for a moment, e.g. hitstop on heavy hits.
+//!
+//! Gameplay systems read `Res<Time>`, which follows `Time<Virtual>` and so is dilated.
+//! Physics runs on `Time<Physics>`, which is dilated by the same amount.
+//! UI that has to keep running reads `Res<Time<Real>>` instead.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    death::DeathEvent,
+    health::{DamageDealtEvent, Health},
+};
+use crate::{screen::Screen, AppSet};
+
+/// Hits dealing at least this much damage, or critical hits, cause hitstop.
+const HITSTOP_DAMAGE: f32 = 15.0;
+const HITSTOP_DURATION: Duration = Duration::from_millis(60);
+const HITSTOP_SCALE: f32 = 0.05;
+/// Hits that kill cause a slightly longer hitstop.
+const KILL_HITSTOP_DURATION: Duration = Duration::from_millis(100);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<DilateTime>();
+    app.init_resource::<TimeDilation>();
+    app.register_type::<TimeDilation>();
+    app.register_type::<SlowMotionOnDeath>();
+    app.add_systems(
+        Update,
+        (hitstop_on_heavy_hits, slow_motion_on_death)
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, update_time_dilation);
+    app.add_systems(OnExit(Screen::Playing), reset_time_dilation);
+}
+
+/// Request gameplay time to run at `scale` for `duration` of real time.
+/// When several requests overlap, the slowest one wins.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DilateTime {
+    pub scale: f32,
+    pub duration: Duration,
+}
+
+impl DilateTime {
+    pub fn hitstop(duration: Duration) -> Self {
+        DilateTime {
+            scale: HITSTOP_SCALE,
+            duration,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy)]
+struct ActiveDilation {
+    scale: f32,
+    remaining: Duration,
+}
+
+/// The dilations currently in effect.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct TimeDilation {
+    active: Vec<ActiveDilation>,
+}
+
+impl TimeDilation {
+    /// The speed gameplay time currently runs at.
+    pub fn scale(&self) -> f32 {
+        self.active
+            .iter()
+            .map(|dilation| dilation.scale)
+            .fold(1.0, f32::min)
+    }
+}
+
+/// Slows down time when the entity dies, e.g. for a boss kill.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct SlowMotionOnDeath {
+    pub scale: f32,
+    /// In real time.
+    pub duration: Duration,
+}
+
+fn hitstop_on_heavy_hits(
+    mut events: EventReader<DamageDealtEvent>,
+    mut dilate_events: EventWriter<DilateTime>,
+) {
+    for event in events.read() {
+        if event.damage_over_time {
+            continue;
+        }
+        if event.killing_blow {
+            dilate_events.send(DilateTime::hitstop(KILL_HITSTOP_DURATION));
+        } else if event.critical || event.amount >= HITSTOP_DAMAGE {
+            dilate_events.send(DilateTime::hitstop(HITSTOP_DURATION));
+        }
+    }
+}
+
+fn slow_motion_on_death(
+    mut events: EventReader<DeathEvent>,
+    mut dilate_events: EventWriter<DilateTime>,
+    query: Query<&SlowMotionOnDeath, With<Health>>,
+) {
+    for event in events.read() {
+        if let Ok(slow_motion) = query.get(event.entity) {
+            dilate_events.send(DilateTime {
+                scale: slow_motion.scale,
+                duration: slow_motion.duration,
+            });
+        }
+    }
+}
+
+/// Runs after gameplay, so the new speed applies from the next frame on.
+fn update_time_dilation(
+    real_time: Res<Time<Real>>,
+    mut events: EventReader<DilateTime>,
+    mut dilation: ResMut<TimeDilation>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    for active in &mut dilation.active {
+        active.remaining = active.remaining.saturating_sub(real_time.delta());
+    }
+    dilation.active.retain(|active| !active.remaining.is_zero());
+    for event in events.read() {
+        dilation.active.push(ActiveDilation {
+            scale: event.scale.max(0.0),
+            remaining: event.duration,
+        });
+    }
+
+    let scale = dilation.scale();
+    if virtual_time.relative_speed() != scale {
+        virtual_time.set_relative_speed(scale);
+        physics_time.set_relative_speed(scale);
+    }
+}
+
+fn reset_time_dilation(
+    mut dilation: ResMut<TimeDilation>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    dilation.active.clear();
+    virtual_time.set_relative_speed(1.0);
+    physics_time.set_relative_speed(1.0);
+}
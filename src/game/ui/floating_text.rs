@@ -0,0 +1,212 @@
+//! Floating numbers above entities for the damage and healing they receive.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    game::health::{DamageDealtEvent, DamageKind, HealedEvent},
+    AppSet,
+};
+
+/// Upper bound on floating texts alive at once. Past it, the oldest one is reused.
+const MAX_FLOATING_TEXTS: usize = 64;
+const LIFETIME: Duration = Duration::from_millis(800);
+/// Hits on the same target within this window add up into one number.
+const MERGE_WINDOW: Duration = Duration::from_millis(250);
+const RISE_SPEED: f32 = 40.0;
+const OFFSET: Vec3 = Vec3::new(0.0, 36.0, 20.0);
+const FONT_SIZE: f32 = 20.0;
+const CRITICAL_FONT_SIZE: f32 = 28.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FloatingTextPool>();
+    app.register_type::<FloatingText>();
+    app.add_systems(
+        Update,
+        (show_floating_texts, animate_floating_texts)
+            .chain()
+            .in_set(AppSet::Update),
+    );
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FloatingTextKind {
+    Damage(DamageKind),
+    Critical,
+    Heal,
+}
+
+impl FloatingTextKind {
+    fn color(&self) -> Color {
+        match self {
+            FloatingTextKind::Damage(DamageKind::Physical) => Color::srgb(1.0, 1.0, 1.0),
+            FloatingTextKind::Damage(DamageKind::Fire) => Color::srgb(1.0, 0.5, 0.1),
+            FloatingTextKind::Damage(DamageKind::Poison) => Color::srgb(0.6, 0.9, 0.1),
+            FloatingTextKind::Damage(DamageKind::Spirit) => Color::srgb(0.7, 0.6, 1.0),
+            FloatingTextKind::Critical => Color::srgb(1.0, 0.85, 0.1),
+            FloatingTextKind::Heal => Color::srgb(0.3, 1.0, 0.5),
+        }
+    }
+
+    fn font_size(&self) -> f32 {
+        match self {
+            FloatingTextKind::Critical => CRITICAL_FONT_SIZE,
+            _ => FONT_SIZE,
+        }
+    }
+
+    fn format(&self, amount: f32) -> String {
+        match self {
+            FloatingTextKind::Damage(_) => format!("{:.0}", amount),
+            FloatingTextKind::Critical => format!("{:.0}!", amount),
+            FloatingTextKind::Heal => format!("+{:.0}", amount),
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct FloatingText {
+    target: Entity,
+    kind: FloatingTextKind,
+    amount: f32,
+    origin: Vec3,
+    age: Duration,
+}
+
+/// Floating text entities that finished their animation and can be reused.
+#[derive(Resource, Default)]
+struct FloatingTextPool {
+    free: Vec<Entity>,
+}
+
+fn show_floating_texts(
+    mut commands: Commands,
+    mut pool: ResMut<FloatingTextPool>,
+    mut damage_events: EventReader<DamageDealtEvent>,
+    mut heal_events: EventReader<HealedEvent>,
+    target_query: Query<&Transform, Without<FloatingText>>,
+    mut text_query: Query<(Entity, &mut FloatingText, &mut Text)>,
+) {
+    // Add up this frame's hits first, so they merge even into texts spawned this frame.
+    let mut incoming: HashMap<(Entity, FloatingTextKind), f32> = HashMap::default();
+    for event in damage_events.read() {
+        let kind = if event.critical {
+            FloatingTextKind::Critical
+        } else {
+            FloatingTextKind::Damage(event.kind)
+        };
+        *incoming.entry((event.target, kind)).or_default() += event.amount;
+    }
+    for event in heal_events.read() {
+        *incoming
+            .entry((event.target, FloatingTextKind::Heal))
+            .or_default() += event.amount;
+    }
+
+    let mut text_count = text_query.iter().count();
+    let mut recycled = Vec::new();
+    for ((target, kind), amount) in incoming {
+        let merged = text_query
+            .iter_mut()
+            .filter(|(entity, ..)| !pool.free.contains(entity))
+            .find(|(_, text, ..)| {
+                text.target == target && text.kind == kind && text.age < MERGE_WINDOW
+            });
+        if let Some((_, mut floating_text, mut text)) = merged {
+            floating_text.amount += amount;
+            text.sections[0].value = kind.format(floating_text.amount);
+            continue;
+        }
+
+        let Ok(target_transform) = target_query.get(target) else {
+            continue;
+        };
+        let floating_text = FloatingText {
+            target,
+            kind,
+            amount,
+            origin: target_transform.translation + OFFSET,
+            age: Duration::ZERO,
+        };
+        let text = Text::from_section(
+            kind.format(amount),
+            TextStyle {
+                font_size: kind.font_size(),
+                color: kind.color(),
+                ..default()
+            },
+        );
+        let transform = Transform::from_translation(floating_text.origin);
+
+        let reused = pool.free.pop().or_else(|| {
+            if text_count < MAX_FLOATING_TEXTS {
+                return None;
+            }
+            let oldest = text_query
+                .iter()
+                .filter(|(entity, ..)| !recycled.contains(entity))
+                .max_by_key(|(_, text, _)| text.age)
+                .map(|(entity, ..)| entity);
+            recycled.extend(oldest);
+            oldest
+        });
+        match reused {
+            Some(entity) => {
+                commands.entity(entity).insert((
+                    floating_text,
+                    text,
+                    transform,
+                    Visibility::Inherited,
+                ));
+            }
+            None => {
+                text_count += 1;
+                commands.spawn((
+                    Name::new("FloatingText"),
+                    floating_text,
+                    Text2dBundle {
+                        text,
+                        transform,
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+/// Runs on real time, so floating texts keep moving during hitstop.
+fn animate_floating_texts(
+    time: Res<Time<Real>>,
+    mut pool: ResMut<FloatingTextPool>,
+    mut query: Query<(
+        Entity,
+        &mut FloatingText,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    for (entity, mut floating_text, mut text, mut transform, mut visibility) in &mut query {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        floating_text.age += time.delta();
+        if floating_text.age >= LIFETIME {
+            *visibility = Visibility::Hidden;
+            pool.free.push(entity);
+            continue;
+        }
+
+        let t = floating_text.age.as_secs_f32();
+        transform.translation = floating_text.origin + Vec3::Y * RISE_SPEED * t;
+        let progress = t / LIFETIME.as_secs_f32();
+        text.sections[0]
+            .style
+            .color
+            .set_alpha(1.0 - progress * progress);
+    }
+}
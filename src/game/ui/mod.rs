@@ -1,11 +1,25 @@
 use bevy::prelude::*;
 
-use super::health::Health;
+use super::{
+    ability::{Casting, Qi},
+    charge::Charging,
+    dash::Dash,
+    health::{Health, Shield},
+};
 use status_bar::plugin::StatusBarPlugin;
 
+pub mod floating_text;
 pub mod percentage;
 pub mod status_bar;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(StatusBarPlugin::<Health>::default());
+    app.add_plugins((
+        floating_text::plugin,
+        StatusBarPlugin::<Health>::default(),
+        StatusBarPlugin::<Shield>::default(),
+        StatusBarPlugin::<Charging>::default(),
+        StatusBarPlugin::<Qi>::default(),
+        StatusBarPlugin::<Casting>::default(),
+        StatusBarPlugin::<Dash>::default(),
+    ));
 }
@@ -21,6 +21,12 @@ impl Percentage {
 
 pub trait AsPercentage {
     fn percentage(&self) -> Percentage;
+
+    /// How many equal segments a bar showing the percentage is split into,
+    /// e.g. one per stored charge.
+    fn segments(&self) -> u32 {
+        1
+    }
 }
 
 impl AsPercentage for Percentage {
@@ -10,6 +10,8 @@ pub struct StatusBarMaterial {
     pub background_color: LinearRgba,
     #[uniform(0)]
     pub percent: f32,
+    #[uniform(0)]
+    pub segments: f32,
 }
 
 impl Material2d for StatusBarMaterial {
@@ -18,22 +18,24 @@ impl<T: PercentageComponent> Default for StatusBarPlugin<T> {
 
 impl<T: PercentageComponent> Plugin for StatusBarPlugin<T> {
     fn build(&self, app: &mut App) {
-        app.add_plugins(Material2dPlugin::<StatusBarMaterial>::default())
-            .add_systems(Update, despawn::<T>)
-            .add_systems(
-                PostUpdate,
-                ((spawn::<T>), (follow_owner::<T>, update::<T>)).chain(),
-            );
+        // The material is shared by the status bars of every `T`.
+        if !app.is_plugin_added::<Material2dPlugin<StatusBarMaterial>>() {
+            app.add_plugins(Material2dPlugin::<StatusBarMaterial>::default());
+        }
+        app.add_systems(Update, despawn::<T>).add_systems(
+            PostUpdate,
+            ((spawn::<T>), (follow_owner::<T>, update::<T>)).chain(),
+        );
     }
 }
 
 #[derive(Component)]
-pub struct StatusBarOwner(Entity);
+pub struct StatusBarOwner<T: PercentageComponent>(Entity, PhantomData<T>);
 
 #[derive(Bundle)]
-pub struct StatusBarBundle {
+pub struct StatusBarBundle<T: PercentageComponent> {
     material_mesh_bundle: MaterialMesh2dBundle<StatusBarMaterial>,
-    owner: StatusBarOwner,
+    owner: StatusBarOwner<T>,
 }
 
 fn spawn<T: PercentageComponent>(
@@ -53,6 +55,7 @@ fn spawn<T: PercentageComponent>(
                     foreground_color: status_bar_definition.foreground_color.into(),
                     background_color: status_bar_definition.background_color.into(),
                     percent: percentage_component.percentage().value(),
+                    segments: percentage_component.segments() as f32,
                 }),
                 transform: Transform {
                     translation: transform.translation + status_bar_definition.offset,
@@ -65,7 +68,7 @@ fn spawn<T: PercentageComponent>(
                 },
                 ..default()
             },
-            owner: StatusBarOwner(entity),
+            owner: StatusBarOwner::<T>(entity, PhantomData),
         });
     }
 }
@@ -73,12 +76,12 @@ fn spawn<T: PercentageComponent>(
 fn update<T: PercentageComponent>(
     mut status_bar_materials: ResMut<Assets<StatusBarMaterial>>,
     status_bar_query: Query<
-        (&Handle<StatusBarMaterial>, &StatusBarOwner),
+        (&Handle<StatusBarMaterial>, &StatusBarOwner<T>),
         Without<StatusBarDefinition<T>>,
     >,
     owner_percentage_component_query: Query<&T>,
 ) {
-    for (material_handle, &StatusBarOwner(owner_entity)) in status_bar_query.iter() {
+    for (material_handle, &StatusBarOwner(owner_entity, _)) in status_bar_query.iter() {
         let material = status_bar_materials
             .get_mut(material_handle)
             .expect("StatusBarMaterial missing");
@@ -86,14 +89,15 @@ fn update<T: PercentageComponent>(
             continue;
         };
         material.percent = health.percentage().value();
+        material.segments = health.segments() as f32;
     }
 }
 
 fn follow_owner<T: PercentageComponent>(
-    mut bar_query: Query<(&mut Transform, &StatusBarOwner), Without<StatusBarDefinition<T>>>,
+    mut bar_query: Query<(&mut Transform, &StatusBarOwner<T>), Without<StatusBarDefinition<T>>>,
     owner_query: Query<(&Transform, &StatusBarDefinition<T>)>,
 ) {
-    for (mut transform, &StatusBarOwner(owner_entity)) in bar_query.iter_mut() {
+    for (mut transform, &StatusBarOwner(owner_entity, _)) in bar_query.iter_mut() {
         let Ok((owner_transform, owner_bar_definition)) = owner_query.get(owner_entity) else {
             continue;
         };
@@ -104,10 +108,10 @@ fn follow_owner<T: PercentageComponent>(
 
 fn despawn<T: PercentageComponent>(
     mut commands: Commands,
-    bar_query: Query<(Entity, &StatusBarOwner), Without<StatusBarDefinition<T>>>,
+    bar_query: Query<(Entity, &StatusBarOwner<T>), Without<StatusBarDefinition<T>>>,
     owner_query: Query<&StatusBarDefinition<T>>,
 ) {
-    for (bar_entity, &StatusBarOwner(owner_entity)) in bar_query.iter() {
+    for (bar_entity, &StatusBarOwner(owner_entity, _)) in bar_query.iter() {
         if owner_query.get(owner_entity).is_err() {
             commands.entity(bar_entity).despawn();
         };
@@ -0,0 +1,535 @@
+//! Weapons an entity carries, switches between and attacks with.
+//!
+//! Each weapon is plain data: a primary and a secondary [`AttackDefinition`].
+//! Adding a weapon means adding a constructor here, no new systems.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    assets::ImageKey,
+    attack::AttackController,
+    charge::{ChargeDefinition, ChargeStage, ChargingBundle},
+    combo::Combo,
+    health::DamageKind,
+    hitbox::{Hitbox, HitboxShape},
+    knockback::Knockback,
+    projectile::{ProjectileDefinition, ProjectileEffect},
+};
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<WeaponInventory>();
+    app.add_systems(
+        Update,
+        (
+            tick_weapon_cooldowns.in_set(AppSet::TickTimers),
+            switch_weapon.in_set(AppSet::Update),
+        ),
+    );
+}
+
+/// Which of a weapon's two attacks is used.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttackSlot {
+    Primary,
+    Secondary,
+}
+
+/// Everything needed to spawn a single attack.
+#[derive(Reflect, Clone)]
+pub struct AttackDefinition {
+    pub name: String,
+    pub damage: f32,
+    pub kind: DamageKind,
+    pub knockback: Option<Knockback>,
+    /// Distance from the attacker to the attack's sprite, or to where projectiles spawn.
+    pub reach: f32,
+    /// Relative to the attacker, facing the look direction. Left unset for projectiles.
+    pub hitbox: Option<Hitbox>,
+    /// Time between the press and the hitbox appearing.
+    pub windup: Duration,
+    /// How long the hitbox stays active.
+    pub lifetime: Duration,
+    /// Time after the hitbox disappears before the attacker can act again.
+    pub recovery: Duration,
+    /// Time before the same slot, or the dash attack for a dash attack, can be used again.
+    pub cooldown: Duration,
+    /// Time after the attacker is ready during which a press chains into the next step of the combo.
+    pub chain_window: Duration,
+    /// If set, the attack is charged while the button is held and fires on release.
+    pub charge: Option<ChargeDefinition>,
+    /// If set, the attack fires a projectile instead of spawning a hitbox in front of the attacker.
+    pub projectile: Option<ProjectileDefinition>,
+    pub sprite: ImageKey,
+    pub color: Color,
+    pub scale: f32,
+    /// Number of frames in the sprite's horizontal strip.
+    pub frames: usize,
+    pub frame_interval: Duration,
+}
+
+/// A weapon's attacks. Each slot is a combo chain, the steps are used in order.
+#[derive(Reflect, Clone)]
+pub struct WeaponDefinition {
+    pub name: String,
+    pub primary: Vec<AttackDefinition>,
+    pub secondary: Vec<AttackDefinition>,
+    /// Used instead of either slot when attacking during or right after a dash.
+    pub dash_attack: Option<AttackDefinition>,
+}
+
+impl WeaponDefinition {
+    pub fn chain(&self, slot: AttackSlot) -> &[AttackDefinition] {
+        match slot {
+            AttackSlot::Primary => &self.primary,
+            AttackSlot::Secondary => &self.secondary,
+        }
+    }
+
+    /// Two quick slashes into a heavier finisher, and a charged cleave.
+    pub fn sword() -> Self {
+        let slash = AttackDefinition {
+            name: "SwordSlash".to_string(),
+            damage: 10.0,
+            kind: DamageKind::Physical,
+            knockback: Some(Knockback {
+                strength: 400.0,
+                hitstun: Duration::from_millis(250),
+            }),
+            reach: 50.0,
+            hitbox: Some(Hitbox::new(
+                HitboxShape::SweepingArc {
+                    radius: 66.0,
+                    angle: 2.1,
+                },
+                Vec2::ZERO,
+            )),
+            windup: Duration::from_millis(60),
+            lifetime: Duration::from_millis(180),
+            recovery: Duration::from_millis(200),
+            chain_window: Duration::from_millis(400),
+            cooldown: Duration::ZERO,
+            charge: None,
+            projectile: None,
+            sprite: ImageKey::BaseAttack,
+            color: Color::WHITE,
+            scale: 2.0,
+            frames: 6,
+            frame_interval: Duration::from_millis(60),
+        };
+        WeaponDefinition {
+            name: "Sword".to_string(),
+            primary: vec![
+                slash.clone(),
+                AttackDefinition {
+                    name: "SwordBackslash".to_string(),
+                    color: Color::srgb(0.9, 0.95, 1.0),
+                    ..slash.clone()
+                },
+                AttackDefinition {
+                    name: "SwordFinisher".to_string(),
+                    damage: 16.0,
+                    knockback: Some(Knockback {
+                        strength: 600.0,
+                        hitstun: Duration::from_millis(400),
+                    }),
+                    reach: 60.0,
+                    hitbox: Some(Hitbox::new(
+                        HitboxShape::Arc {
+                            radius: 76.0,
+                            angle: 2.4,
+                        },
+                        Vec2::ZERO,
+                    )),
+                    windup: Duration::from_millis(120),
+                    recovery: Duration::from_millis(450),
+                    chain_window: Duration::ZERO,
+                    color: Color::srgb(1.0, 0.9, 0.5),
+                    scale: 2.4,
+                    ..slash.clone()
+                },
+            ],
+            dash_attack: Some(AttackDefinition {
+                name: "SwordDashStrike".to_string(),
+                damage: 14.0,
+                knockback: Some(Knockback {
+                    strength: 450.0,
+                    hitstun: Duration::from_millis(300),
+                }),
+                hitbox: Some(Hitbox::new(
+                    HitboxShape::Capsule {
+                        length: 80.0,
+                        radius: 14.0,
+                    },
+                    Vec2::new(45.0, 0.0),
+                )),
+                windup: Duration::ZERO,
+                lifetime: Duration::from_millis(150),
+                recovery: Duration::from_millis(300),
+                chain_window: Duration::ZERO,
+                cooldown: Duration::from_millis(800),
+                color: Color::srgb(0.8, 0.95, 1.0),
+                ..slash.clone()
+            }),
+            secondary: vec![AttackDefinition {
+                name: "SwordCleave".to_string(),
+                damage: 18.0,
+                knockback: Some(Knockback {
+                    strength: 700.0,
+                    hitstun: Duration::from_millis(400),
+                }),
+                reach: 60.0,
+                hitbox: Some(Hitbox::new(
+                    HitboxShape::Compound(vec![
+                        Hitbox::new(
+                            HitboxShape::SweepingArc {
+                                radius: 70.0,
+                                angle: 3.2,
+                            },
+                            Vec2::ZERO,
+                        ),
+                        Hitbox::new(HitboxShape::Circle { radius: 22.0 }, Vec2::new(60.0, 0.0)),
+                    ]),
+                    Vec2::ZERO,
+                )),
+                windup: Duration::from_millis(150),
+                lifetime: Duration::from_millis(240),
+                recovery: Duration::from_millis(400),
+                cooldown: Duration::from_millis(900),
+                chain_window: Duration::ZERO,
+                charge: Some(ChargeDefinition {
+                    stages: vec![
+                        ChargeStage {
+                            hold: Duration::from_millis(400),
+                            damage_multiplier: 1.5,
+                            reach_multiplier: 1.2,
+                            knockback_multiplier: 1.5,
+                        },
+                        ChargeStage {
+                            hold: Duration::from_millis(1000),
+                            damage_multiplier: 2.5,
+                            reach_multiplier: 1.5,
+                            knockback_multiplier: 2.0,
+                        },
+                    ],
+                    move_speed_multiplier: 0.4,
+                }),
+                color: Color::srgb(1.0, 0.8, 0.6),
+                scale: 2.6,
+                frame_interval: Duration::from_millis(80),
+                ..slash
+            }],
+        }
+    }
+
+    /// Long, narrow spirit thrusts, and a short-ranged burning sweep.
+    pub fn spear() -> Self {
+        let thrust = AttackDefinition {
+            name: "SpearThrust".to_string(),
+            damage: 7.0,
+            kind: DamageKind::Spirit,
+            knockback: Some(Knockback {
+                strength: 250.0,
+                hitstun: Duration::from_millis(150),
+            }),
+            reach: 80.0,
+            hitbox: Some(Hitbox::new(
+                HitboxShape::Capsule {
+                    length: 70.0,
+                    radius: 8.0,
+                },
+                Vec2::new(55.0, 0.0),
+            )),
+            windup: Duration::from_millis(40),
+            lifetime: Duration::from_millis(150),
+            recovery: Duration::from_millis(150),
+            chain_window: Duration::from_millis(300),
+            cooldown: Duration::ZERO,
+            charge: None,
+            projectile: None,
+            sprite: ImageKey::BaseAttack,
+            color: Color::srgb(0.7, 0.6, 1.0),
+            scale: 1.6,
+            frames: 6,
+            frame_interval: Duration::from_millis(50),
+        };
+        WeaponDefinition {
+            name: "Spear".to_string(),
+            primary: vec![
+                thrust.clone(),
+                thrust.clone(),
+                AttackDefinition {
+                    name: "SpearLunge".to_string(),
+                    damage: 12.0,
+                    knockback: Some(Knockback {
+                        strength: 500.0,
+                        hitstun: Duration::from_millis(300),
+                    }),
+                    reach: 100.0,
+                    hitbox: Some(Hitbox::new(
+                        HitboxShape::Capsule {
+                            length: 100.0,
+                            radius: 10.0,
+                        },
+                        Vec2::new(70.0, 0.0),
+                    )),
+                    windup: Duration::from_millis(100),
+                    recovery: Duration::from_millis(400),
+                    chain_window: Duration::ZERO,
+                    ..thrust.clone()
+                },
+            ],
+            dash_attack: Some(AttackDefinition {
+                name: "SpearCharge".to_string(),
+                damage: 15.0,
+                knockback: Some(Knockback {
+                    strength: 650.0,
+                    hitstun: Duration::from_millis(350),
+                }),
+                reach: 110.0,
+                hitbox: Some(Hitbox::new(
+                    HitboxShape::Capsule {
+                        length: 120.0,
+                        radius: 10.0,
+                    },
+                    Vec2::new(80.0, 0.0),
+                )),
+                windup: Duration::ZERO,
+                recovery: Duration::from_millis(350),
+                chain_window: Duration::ZERO,
+                cooldown: Duration::from_millis(1000),
+                color: Color::srgb(0.85, 0.75, 1.0),
+                ..thrust.clone()
+            }),
+            secondary: vec![AttackDefinition {
+                name: "SpearSweep".to_string(),
+                damage: 6.0,
+                kind: DamageKind::Fire,
+                knockback: Some(Knockback {
+                    strength: 500.0,
+                    hitstun: Duration::from_millis(300),
+                }),
+                reach: 35.0,
+                hitbox: Some(Hitbox::new(
+                    HitboxShape::Ring {
+                        inner_radius: 15.0,
+                        outer_radius: 50.0,
+                    },
+                    Vec2::ZERO,
+                )),
+                windup: Duration::from_millis(120),
+                lifetime: Duration::from_millis(220),
+                recovery: Duration::from_millis(300),
+                cooldown: Duration::from_millis(700),
+                chain_window: Duration::ZERO,
+                color: Color::srgb(1.0, 0.5, 0.1),
+                scale: 2.4,
+                frame_interval: Duration::from_millis(70),
+                ..thrust
+            }],
+        }
+    }
+
+    /// Homing spirit talismans, and a slow piercing one that bounces and bursts into flames.
+    pub fn talisman() -> Self {
+        let talisman = AttackDefinition {
+            name: "Talisman".to_string(),
+            damage: 6.0,
+            kind: DamageKind::Spirit,
+            knockback: None,
+            reach: 20.0,
+            hitbox: None,
+            windup: Duration::from_millis(80),
+            lifetime: Duration::ZERO,
+            recovery: Duration::from_millis(300),
+            chain_window: Duration::ZERO,
+            cooldown: Duration::ZERO,
+            charge: None,
+            projectile: Some(ProjectileDefinition {
+                speed: 350.0,
+                radius: 6.0,
+                lifetime: Duration::from_millis(1500),
+                pierce: 0,
+                bounces: 0,
+                homing: 4.0,
+                on_hit: None,
+                on_expire: None,
+            }),
+            sprite: ImageKey::BaseAttack,
+            color: Color::srgb(1.0, 0.9, 0.4),
+            scale: 0.8,
+            frames: 6,
+            frame_interval: Duration::from_millis(60),
+        };
+        WeaponDefinition {
+            name: "Talisman".to_string(),
+            secondary: vec![AttackDefinition {
+                name: "BurstingTalisman".to_string(),
+                damage: 8.0,
+                kind: DamageKind::Physical,
+                knockback: Some(Knockback {
+                    strength: 200.0,
+                    hitstun: Duration::from_millis(150),
+                }),
+                windup: Duration::from_millis(150),
+                recovery: Duration::from_millis(400),
+                cooldown: Duration::from_millis(1500),
+                projectile: Some(ProjectileDefinition {
+                    speed: 250.0,
+                    radius: 8.0,
+                    lifetime: Duration::from_secs(2),
+                    pierce: 3,
+                    bounces: 2,
+                    homing: 0.0,
+                    on_hit: None,
+                    on_expire: Some(ProjectileEffect::DamageZone {
+                        damage: 10.0,
+                        kind: DamageKind::Fire,
+                        radius: 32.0,
+                        lifetime: Duration::from_millis(150),
+                    }),
+                }),
+                color: Color::srgb(1.0, 0.5, 0.1),
+                scale: 1.2,
+                ..talisman.clone()
+            }],
+            primary: vec![talisman],
+            dash_attack: None,
+        }
+    }
+}
+
+/// A carried weapon and the remaining cooldowns of its attacks.
+#[derive(Reflect, Clone)]
+pub struct Weapon {
+    pub definition: WeaponDefinition,
+    primary_cooldown: Duration,
+    secondary_cooldown: Duration,
+    dash_attack_cooldown: Duration,
+}
+
+impl Weapon {
+    pub fn new(definition: WeaponDefinition) -> Self {
+        Weapon {
+            definition,
+            primary_cooldown: Duration::ZERO,
+            secondary_cooldown: Duration::ZERO,
+            dash_attack_cooldown: Duration::ZERO,
+        }
+    }
+
+    pub fn chain(&self, slot: AttackSlot) -> &[AttackDefinition] {
+        self.definition.chain(slot)
+    }
+
+    pub fn is_ready(&self, slot: AttackSlot) -> bool {
+        match slot {
+            AttackSlot::Primary => self.primary_cooldown.is_zero(),
+            AttackSlot::Secondary => self.secondary_cooldown.is_zero(),
+        }
+    }
+
+    /// Start the slot's cooldown, divided by `speed`, and return the attack of the given
+    /// combo step, unless the slot is still cooling down.
+    pub fn try_attack(
+        &mut self,
+        slot: AttackSlot,
+        step: usize,
+        speed: f32,
+    ) -> Option<&AttackDefinition> {
+        let (cooldown, chain) = match slot {
+            AttackSlot::Primary => (&mut self.primary_cooldown, &self.definition.primary),
+            AttackSlot::Secondary => (&mut self.secondary_cooldown, &self.definition.secondary),
+        };
+        if !cooldown.is_zero() {
+            return None;
+        }
+        let attack = chain.get(step)?;
+        *cooldown = attack.cooldown.div_f32(speed.max(f32::EPSILON));
+        Some(attack)
+    }
+
+    /// Start the dash attack's own cooldown, divided by `speed`, and return the dash attack,
+    /// unless the weapon has none or it is still cooling down.
+    pub fn try_dash_attack(&mut self, speed: f32) -> Option<&AttackDefinition> {
+        if !self.dash_attack_cooldown.is_zero() {
+            return None;
+        }
+        let attack = self.definition.dash_attack.as_ref()?;
+        self.dash_attack_cooldown = attack.cooldown.div_f32(speed.max(f32::EPSILON));
+        Some(attack)
+    }
+
+    fn apply_delta_time(&mut self, delta: Duration) {
+        self.primary_cooldown = self.primary_cooldown.saturating_sub(delta);
+        self.secondary_cooldown = self.secondary_cooldown.saturating_sub(delta);
+        self.dash_attack_cooldown = self.dash_attack_cooldown.saturating_sub(delta);
+    }
+}
+
+/// The weapons an entity carries. Only the equipped one is used to attack.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct WeaponInventory {
+    pub weapons: Vec<Weapon>,
+    pub equipped: usize,
+}
+
+impl WeaponInventory {
+    pub fn new(weapons: impl IntoIterator<Item = WeaponDefinition>) -> Self {
+        WeaponInventory {
+            weapons: weapons.into_iter().map(Weapon::new).collect(),
+            equipped: 0,
+        }
+    }
+
+    pub fn equipped(&self) -> Option<&Weapon> {
+        self.weapons.get(self.equipped)
+    }
+
+    pub fn equipped_mut(&mut self) -> Option<&mut Weapon> {
+        self.weapons.get_mut(self.equipped)
+    }
+
+    /// Equip the next weapon, wrapping around to the first one.
+    pub fn switch_to_next(&mut self) {
+        if !self.weapons.is_empty() {
+            self.equipped = (self.equipped + 1) % self.weapons.len();
+        }
+    }
+}
+
+/// Cooldowns keep running for weapons that are not equipped.
+fn tick_weapon_cooldowns(time: Res<Time>, mut query: Query<&mut WeaponInventory>) {
+    for mut inventory in &mut query {
+        for weapon in &mut inventory.weapons {
+            weapon.apply_delta_time(time.delta());
+        }
+    }
+}
+
+fn switch_weapon(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &AttackController,
+        &mut WeaponInventory,
+        Option<&mut Combo>,
+    )>,
+) {
+    for (entity, controller, mut inventory, combo) in &mut query {
+        if !controller.switch_weapon {
+            continue;
+        }
+        inventory.switch_to_next();
+        commands.entity(entity).remove::<ChargingBundle>();
+        if let Some(mut combo) = combo {
+            combo.reset();
+        }
+        if let Some(weapon) = inventory.equipped() {
+            debug!("Equipped {}", weapon.definition.name);
+        }
+    }
+}
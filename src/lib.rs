@@ -21,6 +21,7 @@ impl Plugin for AppPlugin {
                 AppSet::TickTimers,
                 AppSet::PrepareInput,
                 AppSet::RecordInput,
+                AppSet::ConstrainInput,
                 AppSet::Update,
             )
                 .chain(),
@@ -78,6 +79,8 @@ enum AppSet {
     PrepareInput,
     /// Record player input.
     RecordInput,
+    /// Override recorded input, e.g. for stunned entities.
+    ConstrainInput,
     /// Do everything else (consider splitting this into further variants).
     Update,
 }
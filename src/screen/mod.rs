@@ -3,6 +3,7 @@
 mod credits;
 mod loading;
 mod playing;
+mod settings;
 mod splash;
 mod title;
 
@@ -17,6 +18,7 @@ pub(super) fn plugin(app: &mut App) {
         loading::plugin,
         title::plugin,
         credits::plugin,
+        settings::plugin,
         playing::plugin,
     ));
 }
@@ -29,5 +31,6 @@ pub enum Screen {
     Loading,
     Title,
     Credits,
+    Settings,
     Playing,
 }
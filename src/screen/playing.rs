@@ -6,11 +6,13 @@ use super::Screen;
 use crate::game::{
     assets::SoundtrackKey,
     audio::soundtrack::PlaySoundtrack,
+    death::DoneDyingEvent,
+    rng::seed_run,
     spawn::{level::SpawnLevel, player::Player},
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Playing), enter_playing);
+    app.add_systems(OnEnter(Screen::Playing), enter_playing.after(seed_run));
     app.add_systems(OnExit(Screen::Playing), exit_playing);
 
     app.add_systems(
@@ -38,11 +40,16 @@ fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Title);
 }
 
+/// Once the player is done dying, whether it is left behind as a corpse or despawned.
 fn return_to_title_screen_on_player_death(
     mut next_screen: ResMut<NextState<Screen>>,
-    player_query: Query<&Player>,
+    mut events: EventReader<DoneDyingEvent>,
+    player_query: Query<(), With<Player>>,
 ) {
-    if player_query.get_single().is_err() {
+    if events
+        .read()
+        .any(|event| player_query.contains(event.entity))
+    {
         next_screen.set(Screen::Title);
     }
 }
@@ -0,0 +1,311 @@
+//! A settings screen that can be accessed from the title screen, to rebind the player's actions.
+
+use bevy::{
+    input::{gamepad::GamepadEvent, keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+    ui::Val::*,
+};
+use leafwing_input_manager::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::{
+        bindings::{self, BindingConflict, DPadDirection, InputDevice},
+        input::PlayerAction,
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Rebinding>();
+    app.add_systems(OnEnter(Screen::Settings), enter_settings);
+
+    app.register_type::<SettingsAction>();
+    app.add_systems(
+        Update,
+        (
+            handle_settings_action,
+            capture_binding,
+            update_settings_text.run_if(
+                resource_changed::<InputMap<PlayerAction>>.or_else(resource_changed::<Rebinding>),
+            ),
+        )
+            .chain()
+            .run_if(in_state(Screen::Settings)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum SettingsAction {
+    Rebind(PlayerAction),
+    ResetToDefaults,
+    Back,
+}
+
+/// The action waiting for its new binding, and what happened to the last one.
+#[derive(Resource, Default)]
+struct Rebinding {
+    action: Option<PlayerAction>,
+    /// The direction waiting for its key, for actions bound to sticks.
+    direction: Option<DPadDirection>,
+    status: String,
+}
+
+/// The label listing the bindings of an action.
+#[derive(Component)]
+struct BindingsText(PlayerAction);
+
+#[derive(Component)]
+struct StatusText;
+
+fn enter_settings(mut commands: Commands, mut rebinding: ResMut<Rebinding>) {
+    *rebinding = Rebinding::default();
+
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Settings))
+        .with_children(|children| {
+            children.header("Controls");
+
+            for action in PlayerAction::ALL {
+                children.spawn(row()).with_children(|children| {
+                    children.label("").insert(BindingsText(action));
+                    children
+                        .small_button("Rebind")
+                        .insert(SettingsAction::Rebind(action));
+                });
+            }
+
+            children.label("").insert(StatusText);
+
+            children.spawn(row()).with_children(|children| {
+                children
+                    .button("Defaults")
+                    .insert(SettingsAction::ResetToDefaults);
+                children.button("Back").insert(SettingsAction::Back);
+            });
+        });
+}
+
+fn row() -> impl Bundle {
+    (
+        Name::new("Row"),
+        NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+    )
+}
+
+fn handle_settings_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut input_map: ResMut<InputMap<PlayerAction>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut button_query: InteractionQuery<&SettingsAction>,
+) {
+    // While waiting for a binding, clicks are bindings too.
+    if rebinding.action.is_some() {
+        return;
+    }
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                SettingsAction::Rebind(action) if action.is_button() => {
+                    rebinding.action = Some(*action);
+                    rebinding.direction = None;
+                    rebinding.status =
+                        "Press a key, mouse button or gamepad button, or Escape to cancel"
+                            .to_string();
+                }
+                // Sticks keep their stick, their keys are rebound one direction at a time.
+                SettingsAction::Rebind(action) => {
+                    rebinding.action = Some(*action);
+                    rebinding.direction = Some(DPadDirection::Up);
+                    rebinding.status = format!(
+                        "Press a key for {}, or Escape to cancel",
+                        direction_name(*action, DPadDirection::Up)
+                    );
+                }
+                SettingsAction::ResetToDefaults => {
+                    *input_map = PlayerAction::default_input_map();
+                    rebinding.status = "Restored the default bindings".to_string();
+                }
+                SettingsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
+
+fn capture_binding(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut mouse_events: EventReader<MouseButtonInput>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap<PlayerAction>>,
+) {
+    // Inputs are always read, so that the ones from before the capture started are never used.
+    let keys = keyboard_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| InputKind::PhysicalKey(event.key_code));
+    let mouse_buttons = mouse_events
+        .read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| InputKind::Mouse(event.button));
+    let gamepad_buttons = gamepad_events.read().filter_map(|event| match event {
+        GamepadEvent::Button(event) if event.value >= 0.5 => {
+            Some(InputKind::GamepadButton(event.button_type))
+        }
+        _ => None,
+    });
+    let input = keys.chain(mouse_buttons).chain(gamepad_buttons).last();
+
+    // The press on the rebind button itself is not a binding.
+    if rebinding.is_changed() {
+        return;
+    }
+    let (Some(action), Some(input)) = (rebinding.action, input) else {
+        return;
+    };
+
+    if input == InputKind::PhysicalKey(KeyCode::Escape) {
+        rebinding.action = None;
+        rebinding.status = match rebinding.direction.take() {
+            Some(DPadDirection::Up) | None => "Kept the previous binding".to_string(),
+            Some(_) => "Kept the remaining directions".to_string(),
+        };
+        return;
+    }
+    let result = match rebinding.direction {
+        Some(_) if InputDevice::of(&input) != InputDevice::MouseKeyboard => {
+            rebinding.status =
+                "Directions can only be bound to keys, press another input or Escape".to_string();
+            return;
+        }
+        Some(direction) => bindings::rebind_direction(&mut input_map, action, direction, input),
+        None => bindings::rebind(&mut input_map, action, input),
+    };
+    match result {
+        Ok(()) => {
+            let bound = match rebinding.direction {
+                Some(direction) => direction_name(action, direction),
+                None => action_name(action).to_string(),
+            };
+            rebinding.status = format!("{bound} is now bound to {}", describe(&input));
+            rebinding.direction = rebinding.direction.and_then(DPadDirection::next);
+            match rebinding.direction {
+                Some(next) => {
+                    rebinding.status += &format!(
+                        ", press a key for {} or Escape",
+                        direction_name(action, next)
+                    );
+                }
+                None => rebinding.action = None,
+            }
+        }
+        Err(BindingConflict(other)) => {
+            // Keep waiting, for another input.
+            rebinding.status = format!(
+                "{} is already bound to {}, press another input or Escape",
+                describe(&input),
+                action_name(other)
+            );
+        }
+    }
+}
+
+fn update_settings_text(
+    input_map: Res<InputMap<PlayerAction>>,
+    rebinding: Res<Rebinding>,
+    bindings_query: Query<(&BindingsText, &Children)>,
+    status_query: Query<&Children, With<StatusText>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (BindingsText(action), children) in &bindings_query {
+        let bindings = if rebinding.action == Some(*action) && rebinding.direction.is_none() {
+            "...".to_string()
+        } else {
+            input_map
+                .get(action)
+                .into_iter()
+                .flatten()
+                .map(describe_binding)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        set_text(
+            children,
+            &mut text_query,
+            format!("{}: {bindings}", action_name(*action)),
+        );
+    }
+    for children in &status_query {
+        set_text(children, &mut text_query, rebinding.status.clone());
+    }
+}
+
+/// Sets the text of a label widget.
+fn set_text(children: &Children, text_query: &mut Query<&mut Text>, value: String) {
+    let mut texts = text_query.iter_many_mut(children);
+    while let Some(mut text) = texts.fetch_next() {
+        text.sections[0].value.clone_from(&value);
+    }
+}
+
+fn action_name(action: PlayerAction) -> &'static str {
+    match action {
+        PlayerAction::Move => "Move",
+        PlayerAction::Look => "Look",
+        PlayerAction::Dash => "Dash",
+        PlayerAction::Interact => "Interact",
+        PlayerAction::AttackPrimary => "Primary attack",
+        PlayerAction::AttackSecondary => "Secondary attack",
+        PlayerAction::AbilityPrimary => "Primary ability",
+        PlayerAction::AbilitySeconary => "Secondary ability",
+        PlayerAction::SwitchWeapon => "Switch weapon",
+        PlayerAction::Block => "Block",
+    }
+}
+
+fn direction_name(action: PlayerAction, direction: DPadDirection) -> String {
+    let direction = match direction {
+        DPadDirection::Up => "up",
+        DPadDirection::Left => "left",
+        DPadDirection::Down => "down",
+        DPadDirection::Right => "right",
+    };
+    format!("{} {direction}", action_name(action))
+}
+
+fn describe_binding(binding: &UserInput) -> String {
+    match binding {
+        UserInput::Single(input) => describe(input),
+        UserInput::Chord(inputs) => inputs.iter().map(describe).collect::<Vec<_>>().join("+"),
+        UserInput::VirtualDPad(dpad) => [dpad.up, dpad.left, dpad.down, dpad.right]
+            .iter()
+            .map(describe)
+            .collect::<Vec<_>>()
+            .join("/"),
+        UserInput::VirtualAxis(axis) => {
+            format!("{}/{}", describe(&axis.negative), describe(&axis.positive))
+        }
+    }
+}
+
+fn describe(input: &InputKind) -> String {
+    match input {
+        InputKind::DualAxis(axis) if *axis == DualAxis::left_stick() => "Left stick".to_string(),
+        InputKind::DualAxis(axis) if *axis == DualAxis::right_stick() => "Right stick".to_string(),
+        InputKind::PhysicalKey(key_code) => {
+            let name = format!("{key_code:?}");
+            name.strip_prefix("Key").unwrap_or(&name).to_string()
+        }
+        InputKind::Mouse(button) => format!("Mouse {button:?}"),
+        input => input.to_string(),
+    }
+}
@@ -7,53 +7,28 @@ use super::{interaction::InteractionPalette, palette::*};
 /// An extension trait for spawning UI widgets.
 pub trait Widgets {
     /// Spawn a simple button with text.
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
+
+    /// Spawn a compact button with text. Smaller than [`Widgets::button`], to fit in rows.
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 
     /// Spawn a simple header label. Bigger than [`Widgets::label`].
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 
     /// Spawn a simple text label.
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 }
 
 impl<T: Spawn> Widgets for T {
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands {
-        let mut entity = self.spawn((
-            Name::new("Button"),
-            ButtonBundle {
-                style: Style {
-                    width: Px(200.0),
-                    height: Px(65.0),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                background_color: BackgroundColor(NODE_BACKGROUND),
-                ..default()
-            },
-            InteractionPalette {
-                none: NODE_BACKGROUND,
-                hovered: BUTTON_HOVERED_BACKGROUND,
-                pressed: BUTTON_PRESSED_BACKGROUND,
-            },
-        ));
-        entity.with_children(|children| {
-            children.spawn((
-                Name::new("Button Text"),
-                TextBundle::from_section(
-                    text,
-                    TextStyle {
-                        font_size: 40.0,
-                        color: BUTTON_TEXT,
-                        ..default()
-                    },
-                ),
-            ));
-        });
-        entity
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
+        spawn_button(self, text, Px(200.0), Px(65.0), 40.0)
     }
 
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
+        spawn_button(self, text, Px(140.0), Px(36.0), 24.0)
+    }
+
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Header"),
             NodeBundle {
@@ -84,7 +59,7 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Label"),
             NodeBundle {
@@ -114,15 +89,57 @@ impl<T: Spawn> Widgets for T {
     }
 }
 
+fn spawn_button<'a, T: Spawn>(
+    spawner: &'a mut T,
+    text: impl Into<String>,
+    width: Val,
+    height: Val,
+    font_size: f32,
+) -> EntityCommands<'a> {
+    let mut entity = spawner.spawn((
+        Name::new("Button"),
+        ButtonBundle {
+            style: Style {
+                width,
+                height,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(NODE_BACKGROUND),
+            ..default()
+        },
+        InteractionPalette {
+            none: NODE_BACKGROUND,
+            hovered: BUTTON_HOVERED_BACKGROUND,
+            pressed: BUTTON_PRESSED_BACKGROUND,
+        },
+    ));
+    entity.with_children(|children| {
+        children.spawn((
+            Name::new("Button Text"),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size,
+                    color: BUTTON_TEXT,
+                    ..default()
+                },
+            ),
+        ));
+    });
+    entity
+}
+
 /// An extension trait for spawning UI containers.
 pub trait Containers {
     /// Spawns a root node that covers the full screen
     /// and centers its content horizontally and vertically.
-    fn ui_root(&mut self) -> EntityCommands;
+    fn ui_root(&mut self) -> EntityCommands<'_>;
 }
 
 impl Containers for Commands<'_, '_> {
-    fn ui_root(&mut self) -> EntityCommands {
+    fn ui_root(&mut self) -> EntityCommands<'_> {
         self.spawn((
             Name::new("UI Root"),
             NodeBundle {
@@ -147,17 +164,17 @@ impl Containers for Commands<'_, '_> {
 /// are able to spawn entities.
 /// Ideally, this trait should be [part of Bevy itself](https://github.com/bevyengine/bevy/issues/14231).
 trait Spawn {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands;
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_>;
 }
 
 impl Spawn for Commands<'_, '_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }
 
 impl Spawn for ChildBuilder<'_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }